@@ -0,0 +1,48 @@
+//! Extracting one field from a large document with `parse_field`, against
+//! a full `root` parse followed by `JsonValue::pointer`.
+//!
+//! Run with `cargo bench --bench parse_field`.
+
+use jsonparse::parser::{self, JsonValue};
+use std::time::{Duration, Instant};
+
+const ITEMS: usize = 50_000;
+const RUNS: u32 = 5;
+
+fn document() -> String {
+    let mut doc = String::from(r#"{"items": ["#);
+    for n in 0..ITEMS {
+        if n > 0 {
+            doc.push(',');
+        }
+        doc.push_str(&format!(
+            r#"{{"id": {}, "name": "item {}", "tags": ["a", "b", "c"], "price": {}.25, "ok": true, "extra": null}}"#,
+            n, n, n
+        ));
+    }
+    doc.push_str(r#"], "meta": {"target": "found"}}"#);
+    doc
+}
+
+fn time<F: FnMut() -> Option<JsonValue>>(name: &str, mut f: F) {
+    let mut best = Duration::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        let found = f();
+        best = best.min(start.elapsed());
+        assert_eq!(found, Some(JsonValue::Str(String::from("found"))));
+    }
+    println!("{:<24} {:>10.2?}", name, best);
+}
+
+fn main() {
+    let doc = document();
+    println!("document: {} bytes, best of {} runs", doc.len(), RUNS);
+    time("root + pointer", || {
+        let (_, v) = parser::root(&doc).unwrap();
+        v.pointer("/meta/target").cloned()
+    });
+    time("parse_field", || {
+        parser::parse_field(&doc, "/meta/target").unwrap()
+    });
+}
@@ -0,0 +1,68 @@
+use crate::parser::JsonValue;
+
+fn sextet(b: u8) -> Option<u32> {
+    let v = match b {
+        b'A'..=b'Z' => b - b'A',
+        b'a'..=b'z' => b - b'a' + 26,
+        b'0'..=b'9' => b - b'0' + 52,
+        b'+' => 62,
+        b'/' => 63,
+        _ => return None,
+    };
+    Some(v as u32)
+}
+
+/// Standard-alphabet base64 with mandatory `=` padding.
+// `usize::is_multiple_of` would need Rust 1.87
+#[allow(clippy::manual_is_multiple_of)]
+fn decode(s: &str) -> Option<Vec<u8>> {
+    let bytes = s.as_bytes();
+    if bytes.len() % 4 != 0 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(bytes.len() / 4 * 3);
+    for (n, quad) in bytes.chunks(4).enumerate() {
+        let last = n + 1 == bytes.len() / 4;
+        let pad = quad.iter().rev().take_while(|&&b| b == b'=').count();
+        if pad > 2 || (pad > 0 && !last) {
+            return None;
+        }
+        let mut acc = 0u32;
+        for &b in &quad[..4 - pad] {
+            acc = acc << 6 | sextet(b)?;
+        }
+        acc <<= 6 * pad as u32;
+        out.extend_from_slice(&acc.to_be_bytes()[1..4 - pad]);
+    }
+    Some(out)
+}
+
+impl JsonValue {
+    /// Decode a string value as base64; `None` if this is not a string or
+    /// the contents are not padded standard base64.
+    pub fn as_base64_bytes(&self) -> Option<Vec<u8>> {
+        match self {
+            JsonValue::Str(s) => decode(s),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::root;
+
+    #[test]
+    fn test_as_base64_bytes() {
+        let (_, v) =
+            root(r#"{"a": "aGVsbG8=", "b": "aGk=", "d": "aGVsbG8", "e": "aG=k", "f": 1}"#).unwrap();
+        assert_eq!(
+            v.get("a").unwrap().as_base64_bytes(),
+            Some(b"hello".to_vec())
+        );
+        assert_eq!(v.get("b").unwrap().as_base64_bytes(), Some(b"hi".to_vec()));
+        assert_eq!(v.get("d").unwrap().as_base64_bytes(), None);
+        assert_eq!(v.get("e").unwrap().as_base64_bytes(), None);
+        assert_eq!(v.get("f").unwrap().as_base64_bytes(), None);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::parser::JsonValue;
+use crate::pointer::push_token;
+use std::fmt::Write;
+
+fn diff_at(a: &JsonValue, b: &JsonValue, path: &mut String, out: &mut String) {
+    let base = path.len();
+    match (a, b) {
+        (JsonValue::Object(ours), JsonValue::Object(theirs)) => {
+            let mut keys: Vec<&String> = ours.keys().chain(theirs.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for k in keys {
+                push_token(path, k);
+                match (ours.get(k), theirs.get(k)) {
+                    (Some(x), Some(y)) => diff_at(x, y, path, out),
+                    (Some(_), None) => writeln!(out, "removed {}", path).unwrap(),
+                    (None, _) => writeln!(out, "added {}", path).unwrap(),
+                }
+                path.truncate(base);
+            }
+        }
+        (JsonValue::Array(ours), JsonValue::Array(theirs)) => {
+            for i in 0..ours.len().max(theirs.len()) {
+                push_token(path, &i.to_string());
+                match (ours.get(i), theirs.get(i)) {
+                    (Some(x), Some(y)) => diff_at(x, y, path, out),
+                    (Some(_), None) => writeln!(out, "removed {}", path).unwrap(),
+                    (None, _) => writeln!(out, "added {}", path).unwrap(),
+                }
+                path.truncate(base);
+            }
+        }
+        (a, b) if a != b => {
+            let at = if path.is_empty() { "root" } else { path };
+            writeln!(out, "changed {} from {} to {}", at, a, b).unwrap();
+        }
+        _ => {}
+    }
+}
+
+/// Human-readable list of what changed from `a` to `b`, one line each:
+/// `added <pointer>`, `removed <pointer>` or
+/// `changed <pointer> from <old> to <new>`, with values written as
+/// compact JSON.
+///
+/// Objects are compared by key (members listed in sorted key order) and
+/// arrays by index, so inserting at the front of an array reports every
+/// later element as changed. Identical values give an empty string.
+pub fn diff_summary(a: &JsonValue, b: &JsonValue) -> String {
+    let mut out = String::new();
+    diff_at(a, b, &mut String::new(), &mut out);
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::root;
+
+    #[test]
+    fn test_diff_summary() {
+        let (_, a) = root(
+            r#"{"user": {"name": "x", "a/b": 1}, "items": [1, 2], "flags": [true], "tmp": null}"#,
+        )
+        .unwrap();
+        let (_, b) = root(
+            r#"{"user": {"name": "y", "a/b": 1}, "items": [1, 2, 3], "flags": [], "new": {"k": 1}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            diff_summary(&a, &b),
+            "removed /flags/0\n\
+             added /items/2\n\
+             added /new\n\
+             removed /tmp\n\
+             changed /user/name from \"x\" to \"y\"\n"
+        );
+
+        let (_, c) = root(r#"{"user": {"name": "x", "a/b": [2]}}"#).unwrap();
+        let (_, d) = root(r#"{"user": {"name": "x", "a/b": {"n": 2}}}"#).unwrap();
+        assert_eq!(
+            diff_summary(&c, &d),
+            "changed /user/a~1b from [2] to {\"n\":2}\n"
+        );
+        assert_eq!(diff_summary(&a, &a), "");
+        assert_eq!(
+            diff_summary(&JsonValue::Num(1.0), &JsonValue::Null),
+            "changed root from 1 to null\n"
+        );
+    }
+}
@@ -0,0 +1,30 @@
+#[cfg(feature = "base64")]
+mod base64;
+pub mod diff;
+pub mod merge;
+pub mod parser;
+mod pointer;
+pub mod serializer;
+pub mod stream;
+mod value;
+
+pub use pointer::SubValue;
+pub use value::{KindCounts, NumberError};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_send_sync() {
+        // compile-time only: fails to build if a type stops being Send + Sync
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<parser::JsonValue>();
+        assert_send_sync::<parser::ParseOptions>();
+        assert_send_sync::<serializer::SerializeOptions>();
+        assert_send_sync::<stream::Parser>();
+        assert_send_sync::<stream::DocumentError>();
+        assert_send_sync::<SubValue>();
+        assert_send_sync::<NumberError>();
+    }
+}
@@ -1,4 +1,4 @@
-mod parser;
+use jsonparse::parser;
 
 fn main() {
     let data = "  { \"a\"\t: 42,
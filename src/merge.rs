@@ -0,0 +1,123 @@
+use crate::parser::JsonValue;
+use std::mem;
+
+/// One step on the way from the root to a nested value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+fn merge_at<F>(
+    target: &mut JsonValue,
+    other: JsonValue,
+    path: &mut Vec<PathSegment>,
+    resolve: &mut F,
+) where
+    F: FnMut(&[PathSegment], JsonValue, JsonValue) -> JsonValue,
+{
+    match (target, other) {
+        (JsonValue::Object(ours), JsonValue::Object(theirs)) => {
+            for (k, v) in theirs {
+                match ours.get_mut(&k) {
+                    Some(existing) => {
+                        path.push(PathSegment::Key(k));
+                        merge_at(existing, v, path, resolve);
+                        path.pop();
+                    }
+                    None => {
+                        ours.insert(k, v);
+                    }
+                }
+            }
+        }
+        (target, other) => {
+            let current = mem::replace(target, JsonValue::Null);
+            *target = resolve(path, current, other);
+        }
+    }
+}
+
+impl JsonValue {
+    /// Deep-merge `other` into `self`.
+    ///
+    /// Objects are merged key by key and keys only present in `other` are
+    /// added. Every other clash (scalars, arrays, or mismatched kinds) is
+    /// handed to `resolve` with its path, the current value and the
+    /// incoming one; whatever it returns is stored.
+    pub fn merge_with<F>(&mut self, other: JsonValue, mut resolve: F)
+    where
+        F: FnMut(&[PathSegment], JsonValue, JsonValue) -> JsonValue,
+    {
+        merge_at(self, other, &mut Vec::new(), &mut resolve);
+    }
+
+    /// Add the keys of `defaults` that `self` lacks, at every nesting level
+    /// of matching objects. Existing values always win: scalars, arrays
+    /// and kind mismatches are left alone, and a non-object `self` is not
+    /// touched at all.
+    pub fn fill_defaults(&mut self, defaults: &JsonValue) {
+        self.merge_with(defaults.clone(), |_, ours, _| ours);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::root;
+
+    #[test]
+    fn test_merge_with() {
+        let (_, mut base) = root(r#"{"hits": 1, "stats": {"ok": 2, "tags": ["a"]}}"#).unwrap();
+        let (_, other) =
+            root(r#"{"hits": 10, "stats": {"ok": 3, "err": 1, "tags": ["b"]}}"#).unwrap();
+        let mut paths = Vec::new();
+        base.merge_with(other, |path, ours, theirs| {
+            paths.push(path.to_vec());
+            match (ours, theirs) {
+                (JsonValue::Num(a), JsonValue::Num(b)) => JsonValue::Num(a + b),
+                (_, theirs) => theirs,
+            }
+        });
+        let (_, expected) =
+            root(r#"{"hits": 11, "stats": {"ok": 5, "err": 1, "tags": ["b"]}}"#).unwrap();
+        assert_eq!(base, expected);
+
+        paths.sort_by_key(|p| p.len());
+        assert_eq!(paths.len(), 3);
+        assert_eq!(paths[0], vec![PathSegment::Key(String::from("hits"))]);
+        assert!(paths.contains(&vec![
+            PathSegment::Key(String::from("stats")),
+            PathSegment::Key(String::from("tags"))
+        ]));
+    }
+
+    #[test]
+    fn test_fill_defaults() {
+        let (_, mut config) = root(
+            r#"{"port": 8080, "db": {"host": "db.local", "pool": {"max": 5}}, "tags": ["x"]}"#,
+        )
+        .unwrap();
+        let (_, defaults) = root(
+            r#"{"port": 80, "debug": false, "db": {"host": "localhost", "user": "app",
+                "pool": {"max": 10, "min": 1}}, "tags": ["a", "b"], "log": {"level": "info"}}"#,
+        )
+        .unwrap();
+        config.fill_defaults(&defaults);
+        let (_, expected) = root(
+            r#"{"port": 8080, "debug": false, "db": {"host": "db.local", "user": "app",
+                "pool": {"max": 5, "min": 1}}, "tags": ["x"], "log": {"level": "info"}}"#,
+        )
+        .unwrap();
+        assert_eq!(config, expected);
+
+        let (_, mut mismatch) = root(r#"{"db": "sqlite://file"}"#).unwrap();
+        mismatch.fill_defaults(&defaults);
+        assert_eq!(mismatch.get_str("db"), Some("sqlite://file"));
+        assert_eq!(mismatch.get_f64("port"), Some(80.0));
+
+        let mut scalar = JsonValue::Num(1.0);
+        scalar.fill_defaults(&defaults);
+        assert_eq!(scalar, JsonValue::Num(1.0));
+    }
+}
@@ -1,41 +1,67 @@
+use crate::pointer;
 use nom::{
     branch::alt,
     bytes::complete::{escaped, tag, take_till1, take_while_m_n},
-    character::complete::multispace0,
-    combinator::{map, peek, value as n_value},
-    error::context,
-    multi::separated_list,
-    number::complete::double,
-    sequence::{delimited, preceded, separated_pair},
+    character::complete::{alphanumeric1, char, digit0, digit1, multispace0, one_of},
+    combinator::{cut, map, not, opt, peek, recognize, value as n_value},
+    error::{context, convert_error, ErrorKind, ParseError, VerboseError, VerboseErrorKind},
+    sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
     IResult,
 };
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap, fmt, str::Chars};
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, PartialEq)]
 pub enum JsonValue {
     Str(String),
     Boolean(bool),
     Null,
     Num(f64),
     Array(Vec<JsonValue>),
+    /// Map-backed, so a key that appears more than once keeps its last value
+    /// and is serialized once.
     Object(HashMap<String, JsonValue>),
 }
 
-fn normal(i: &str) -> IResult<&str, &str> {
+/// Object members in key order, so `Debug` output is reproducible.
+struct SortedMembers<'a>(&'a HashMap<String, JsonValue>);
+
+impl fmt::Debug for SortedMembers<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut members: Vec<_> = self.0.iter().collect();
+        members.sort_by(|a, b| a.0.cmp(b.0));
+        f.debug_map().entries(members).finish()
+    }
+}
+
+/// Same shape as a derived `Debug`, except object keys are sorted.
+impl fmt::Debug for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JsonValue::Str(s) => f.debug_tuple("Str").field(s).finish(),
+            JsonValue::Boolean(b) => f.debug_tuple("Boolean").field(b).finish(),
+            JsonValue::Null => f.write_str("Null"),
+            JsonValue::Num(n) => f.debug_tuple("Num").field(n).finish(),
+            JsonValue::Array(arr) => f.debug_tuple("Array").field(arr).finish(),
+            JsonValue::Object(obj) => f.debug_tuple("Object").field(&SortedMembers(obj)).finish(),
+        }
+    }
+}
+
+fn normal<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
     take_till1(|c: char| c == '\\' || c == '"' || c.is_ascii_control())(i)
 }
 
-fn parse_hex(i: &str) -> IResult<&str, &str> {
+fn parse_hex<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
     context(
         "hex string",
         preceded(
-            peek(tag("u")),
-            take_while_m_n(5, 5, |c: char| c.is_ascii_hexdigit() || c == 'u'),
+            char('u'),
+            take_while_m_n(4, 4, |c: char| c.is_ascii_hexdigit()),
         ),
     )(i)
 }
 
-fn escapable(i: &str) -> IResult<&str, &str> {
+fn escapable<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
     context(
         "escaped",
         alt((
@@ -52,110 +78,717 @@ fn escapable(i: &str) -> IResult<&str, &str> {
     )(i)
 }
 
-fn parse_str(i: &str) -> IResult<&str, &str> {
+fn parse_str<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
     escaped(normal, '\\', escapable)(i)
 }
 
-fn string(i: &str) -> IResult<&str, &str> {
+fn string<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
     context(
         "string",
         alt((tag("\"\""), delimited(tag("\""), parse_str, tag("\"")))),
     )(i)
 }
 
-fn boolean(i: &str) -> IResult<&str, bool> {
+/// Like `escapable`, but any character may follow the backslash.
+fn any_escapable<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
+    alt((parse_hex, take_while_m_n(1, 1, |_| true)))(i)
+}
+
+fn lenient_string<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
+    context(
+        "string",
+        alt((
+            tag("\"\""),
+            delimited(tag("\""), escaped(normal, '\\', any_escapable), tag("\"")),
+        )),
+    )(i)
+}
+
+/// The code unit of a `\uXXXX` escape whose `u` was just read, consuming
+/// the four hex digits if they are there.
+fn hex_unit(chars: &mut Chars) -> Option<u32> {
+    let digits: String = chars.clone().take(4).collect();
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    chars.nth(3);
+    u32::from_str_radix(&digits, 16).ok()
+}
+
+/// Character of a `\u` escape, pairing a high surrogate with a following
+/// `\u` low surrogate. Half a pair on its own becomes U+FFFD.
+fn unicode_escape(chars: &mut Chars) -> Option<char> {
+    let unit = hex_unit(chars)?;
+    if !(0xd800..0xe000).contains(&unit) {
+        return char::from_u32(unit);
+    }
+    let mut next = chars.clone();
+    if unit < 0xdc00 && next.next() == Some('\\') && next.next() == Some('u') {
+        if let Some(low @ 0xdc00..=0xdfff) = hex_unit(&mut next) {
+            *chars = next;
+            return char::from_u32(0x10000 + ((unit - 0xd800) << 10) + (low - 0xdc00));
+        }
+    }
+    Some('\u{fffd}')
+}
+
+/// Decode the escapes in the contents of a string literal: `\n` becomes
+/// a newline, `\b` U+0008, `\f` U+000C and so on. Escapes `escapable`
+/// would reject only get here with a lenient `policy`, which decides what
+/// they turn into.
+fn unescape(raw: &str, policy: InvalidEscapePolicy) -> Cow<'_, str> {
+    if !raw.contains('\\') {
+        return Cow::Borrowed(raw);
+    }
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let escaped = match chars.next() {
+            Some(escaped) => escaped,
+            None => break,
+        };
+        let decoded = match escaped {
+            '"' | '\\' | '/' => Some(escaped),
+            'b' => Some('\u{8}'),
+            'f' => Some('\u{c}'),
+            'n' => Some('\n'),
+            'r' => Some('\r'),
+            't' => Some('\t'),
+            'u' => unicode_escape(&mut chars),
+            _ => None,
+        };
+        match decoded {
+            Some(c) => out.push(c),
+            None if policy == InvalidEscapePolicy::Replace => out.push('\u{fffd}'),
+            None => out.push(escaped),
+        }
+    }
+    Cow::Owned(out)
+}
+
+fn string_with<'a, E: ParseError<&'a str>>(
+    i: &'a str,
+    opts: &ParseOptions,
+) -> IResult<&'a str, Cow<'a, str>, E> {
+    let policy = opts.invalid_escape_policy;
+    match policy {
+        InvalidEscapePolicy::Error => map(string, |s| unescape(s, policy))(i),
+        _ => map(lenient_string, |s| unescape(s, policy))(i),
+    }
+}
+
+fn boolean<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, bool, E> {
     let parse_true = n_value(true, tag("true"));
     let parse_false = n_value(false, tag("false"));
     alt((parse_true, parse_false))(i)
 }
 
-fn null(i: &str) -> IResult<&str, JsonValue> {
+fn null<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, JsonValue, E> {
     map(tag("null"), |_| JsonValue::Null)(i)
 }
 
-fn value(i: &str) -> IResult<&str, JsonValue> {
+/// JavaScript's bare `undefined`, read as `null` if the options allow it.
+/// Otherwise it is a hard error rather than a failed alternative, so the
+/// message says what was found.
+fn undefined<'a, E: ParseError<&'a str>>(
+    i: &'a str,
+    opts: &ParseOptions,
+) -> IResult<&'a str, JsonValue, E> {
+    let (rest, _) = terminated(tag("undefined"), not(alphanumeric1))(i)?;
+    if opts.allow_undefined {
+        return Ok((rest, JsonValue::Null));
+    }
+    let e = E::from_error_kind(i, ErrorKind::Tag);
+    Err(nom::Err::Failure(E::add_context(
+        i,
+        "`undefined` is not valid JSON",
+        e,
+    )))
+}
+
+/// What to do with an unknown string escape such as `\q`.
+///
+/// Valid escapes are decoded as usual either way; only the offending
+/// escape is affected.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum InvalidEscapePolicy {
+    /// Reject the input, as strict JSON requires.
+    #[default]
+    Error,
+    /// Drop the backslash and keep the character: `\q` becomes `q`.
+    Literal,
+    /// Replace the whole escape with U+FFFD.
+    Replace,
+}
+
+/// What `root_with` and `root_verbose` do with input left over after the
+/// document (trailing whitespace is always consumed).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TrailingData {
+    /// Fail if anything follows the document.
+    #[default]
+    Forbid,
+    /// Ignore whatever follows; the remaining input returned is empty.
+    Allow,
+    /// Return whatever follows as the remaining input.
+    Return,
+}
+
+/// Knobs that change how input is turned into a `JsonValue`.
+///
+/// `ParseOptions::default()` parses plain JSON, same as `root`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParseOptions {
+    /// Applied to every object key at every nesting level, e.g. to turn
+    /// camelCase keys into snake_case. `None` keeps keys as written.
+    pub key_transform: Option<fn(&str) -> String>,
+    /// Fail on integer literals (no fraction or exponent) that would lose
+    /// precision, i.e. that do not fit an `i64` or are not exactly
+    /// representable as `f64` (beyond 2^53). Off by default.
+    pub reject_imprecise_integers: bool,
+    /// Called with every string value (not keys); returning `Some` replaces
+    /// the plain `JsonValue::Str`, e.g. to reinterpret dates or UUIDs.
+    pub scalar_hook: Option<fn(&str) -> Option<JsonValue>>,
+    /// Handling of unknown escapes in strings and keys.
+    pub invalid_escape_policy: InvalidEscapePolicy,
+    /// Expected number of members of the top-level array or object, used
+    /// to pre-size it. Best effort only: it is not a limit, it is capped at
+    /// what the input could hold, and nested containers grow as usual. `0`
+    /// means no hint.
+    pub initial_capacity_hint: usize,
+    /// Read JavaScript's bare `undefined` as `null`. Off by default, in
+    /// which case `undefined` is rejected with a message naming it.
+    pub allow_undefined: bool,
+    /// Handling of input after the top-level document. Defaults to
+    /// `TrailingData::Forbid`.
+    pub trailing_data: TrailingData,
+}
+
+impl ParseOptions {
+    fn key(&self, k: &str) -> String {
+        match self.key_transform {
+            Some(transform) => transform(k),
+            None => String::from(k),
+        }
+    }
+
+    fn string_value(&self, s: &str) -> JsonValue {
+        self.scalar_hook
+            .and_then(|hook| hook(s))
+            .unwrap_or_else(|| JsonValue::Str(String::from(s)))
+    }
+}
+
+fn is_exact_integer(literal: &str, n: f64) -> bool {
+    match literal.parse::<i64>() {
+        // `as` saturates at the i64 bounds; 2^63 itself is not an i64.
+        Ok(int) => n >= -(2f64.powi(63)) && n < 2f64.powi(63) && n as i64 == int,
+        Err(_) => false,
+    }
+}
+
+/// A number as RFC 8259 spells it: optional minus, no leading zeros or
+/// `+`, and digits on both sides of a decimal point.
+fn number_literal<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, &'a str, E> {
+    recognize(tuple((
+        opt(char('-')),
+        alt((tag("0"), recognize(pair(one_of("123456789"), digit0)))),
+        opt(pair(char('.'), cut(digit1))),
+        opt(tuple((one_of("eE"), opt(one_of("+-")), cut(digit1)))),
+    )))(i)
+}
+
+fn number<'a, E: ParseError<&'a str>>(i: &'a str, opts: &ParseOptions) -> IResult<&'a str, f64, E> {
+    let (rest, literal) = number_literal(i)?;
+    let n: f64 = literal
+        .parse()
+        .expect("the JSON number grammar is a subset of Rust's");
+    if opts.reject_imprecise_integers
+        && !literal.contains(&['.', 'e', 'E'][..])
+        && !is_exact_integer(literal, n)
+    {
+        let e = E::from_error_kind(i, ErrorKind::Verify);
+        return Err(nom::Err::Failure(E::add_context(
+            i,
+            "integer literal is not exactly representable as f64",
+            e,
+        )));
+    }
+    Ok((rest, n))
+}
+
+/// nom's `separated_list`, collecting into a vector pre-sized for
+/// `capacity` items. An item that fails after a separator is a failure,
+/// so its own error is reported instead of the closing bracket's.
+fn separated_list_with_capacity<'a, O, O2, E, F, G>(
+    capacity: usize,
+    sep: G,
+    f: F,
+) -> impl Fn(&'a str) -> IResult<&'a str, Vec<O>, E>
+where
+    F: Fn(&'a str) -> IResult<&'a str, O, E>,
+    G: Fn(&'a str) -> IResult<&'a str, O2, E>,
+    E: ParseError<&'a str>,
+{
+    move |mut i| {
+        let mut res = Vec::with_capacity(capacity);
+        match f(i) {
+            Err(nom::Err::Error(_)) => return Ok((i, res)),
+            Err(e) => return Err(e),
+            Ok((rest, o)) => {
+                res.push(o);
+                i = rest;
+            }
+        }
+        loop {
+            let after_sep = match sep(i) {
+                Err(nom::Err::Error(_)) => return Ok((i, res)),
+                Err(e) => return Err(e),
+                Ok((rest, _)) => rest,
+            };
+            match f(after_sep) {
+                Err(nom::Err::Error(e)) => return Err(nom::Err::Failure(e)),
+                Err(e) => return Err(e),
+                Ok((rest, o)) => {
+                    res.push(o);
+                    i = rest;
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn value<'a, E: ParseError<&'a str>>(
+    i: &'a str,
+    opts: &ParseOptions,
+) -> IResult<&'a str, JsonValue, E> {
     context(
         "value",
         delimited(
             multispace0,
             alt((
-                map(object, JsonValue::Object),
-                map(array, JsonValue::Array),
-                map(string, |s| JsonValue::Str(String::from(s))),
-                map(double, JsonValue::Num),
+                map(|i| object(i, opts, 0), JsonValue::Object),
+                map(|i| array(i, opts, 0), JsonValue::Array),
+                map(|i| string_with(i, opts), |s| opts.string_value(&s)),
+                map(|i| number(i, opts), JsonValue::Num),
                 map(boolean, JsonValue::Boolean),
                 null,
+                |i| undefined(i, opts),
             )),
             multispace0,
         ),
     )(i)
 }
 
-fn array(i: &str) -> IResult<&str, Vec<JsonValue>> {
+fn array<'a, E: ParseError<&'a str>>(
+    i: &'a str,
+    opts: &ParseOptions,
+    capacity: usize,
+) -> IResult<&'a str, Vec<JsonValue>, E> {
+    // every item takes at least two bytes, e.g. `1,`
+    let capacity = capacity.min(i.len() / 2);
     context(
         "array",
         delimited(
             tag("["),
-            separated_list(tag(","), delimited(multispace0, value, multispace0)),
-            tag("]"),
+            separated_list_with_capacity(
+                capacity,
+                tag(","),
+                delimited(multispace0, |i| value(i, opts), multispace0),
+            ),
+            preceded(multispace0, cut(char(']'))),
         ),
     )(i)
 }
 
-fn key(i: &str) -> IResult<&str, &str> {
-    delimited(multispace0, string, multispace0)(i)
+fn key<'a, E: ParseError<&'a str>>(
+    i: &'a str,
+    opts: &ParseOptions,
+) -> IResult<&'a str, Cow<'a, str>, E> {
+    delimited(multispace0, |i| string_with(i, opts), multispace0)(i)
 }
 
-fn object(i: &str) -> IResult<&str, HashMap<String, JsonValue>> {
+fn object<'a, E: ParseError<&'a str>>(
+    i: &'a str,
+    opts: &ParseOptions,
+    capacity: usize,
+) -> IResult<&'a str, HashMap<String, JsonValue>, E> {
+    // every member takes at least five bytes, e.g. `"":1,`
+    let capacity = capacity.min(i.len() / 5);
     context(
         "object",
         delimited(
             tag("{"),
             map(
-                separated_list(
+                separated_list_with_capacity(
+                    0,
                     tag(","),
-                    separated_pair(key, tag(":"), delimited(multispace0, value, multispace0)),
+                    separated_pair(
+                        |i| key(i, opts),
+                        cut(tag(":")),
+                        cut(delimited(multispace0, |i| value(i, opts), multispace0)),
+                    ),
                 ),
-                |tuple_vec: Vec<(&str, JsonValue)>| {
-                    tuple_vec
-                        .into_iter()
-                        .map(|(k, v)| (String::from(k), v))
-                        .collect()
+                move |tuple_vec: Vec<(Cow<str>, JsonValue)>| {
+                    let mut obj = HashMap::with_capacity(capacity);
+                    obj.extend(tuple_vec.into_iter().map(|(k, v)| (opts.key(&k), v)));
+                    obj
                 },
             ),
-            tag("}"),
+            preceded(multispace0, cut(char('}'))),
         ),
     )(i)
 }
 
 pub fn root(i: &str) -> IResult<&str, JsonValue> {
+    root_with(i, &ParseOptions::default())
+}
+
+pub fn root_with<'a>(i: &'a str, opts: &ParseOptions) -> IResult<&'a str, JsonValue> {
+    let (rest, v) = document(i, opts)?;
+    match opts.trailing_data {
+        TrailingData::Forbid if !rest.is_empty() => Err(nom::Err::Failure((rest, ErrorKind::Eof))),
+        TrailingData::Allow => Ok(("", v)),
+        _ => Ok((rest, v)),
+    }
+}
+
+/// Like `root_with`, but a failure is rendered into a readable message.
+///
+/// Bracket mismatches name the container and where it was opened, e.g.
+/// "expected `]` to close array opened at offset 0, found `}`"; anything
+/// else falls back to nom's `convert_error` trace.
+pub fn root_verbose<'a>(i: &'a str, opts: &ParseOptions) -> Result<(&'a str, JsonValue), String> {
+    match document::<VerboseError<&str>>(i, opts) {
+        Ok((rest, _)) if opts.trailing_data == TrailingData::Forbid && !rest.is_empty() => {
+            Err(format!(
+                "unexpected trailing data at offset {}",
+                i.len() - rest.len()
+            ))
+        }
+        Ok((_, v)) if opts.trailing_data == TrailingData::Allow => Ok(("", v)),
+        Ok(done) => Ok(done),
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Err(describe_error(i, e)),
+        Err(nom::Err::Incomplete(_)) => Err(String::from("incomplete input")),
+    }
+}
+
+/// Read `key: <json value>` lines into an object.
+///
+/// The key is the text before the first `:` with surrounding whitespace
+/// removed; the rest of the line must be exactly one JSON value. Blank
+/// lines are skipped and a repeated key keeps its last value. Errors name
+/// the 1-based line they occur on.
+pub fn parse_keyvalue_lines(input: &str) -> Result<JsonValue, String> {
+    let opts = ParseOptions::default();
+    let mut obj = HashMap::new();
+    for (n, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let colon = match line.find(':') {
+            Some(colon) => colon,
+            None => return Err(format!("line {}: expected `key: value`", n + 1)),
+        };
+        let key = line[..colon].trim();
+        if key.is_empty() {
+            return Err(format!("line {}: missing key before `:`", n + 1));
+        }
+        let raw = &line[colon + 1..];
+        match value::<VerboseError<&str>>(raw, &opts) {
+            Ok(("", v)) => {
+                obj.insert(String::from(key), v);
+            }
+            Ok((rest, _)) => return Err(format!("line {}: unexpected `{}`", n + 1, rest)),
+            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+                return Err(format!("line {}: {}", n + 1, describe_error(raw, e)))
+            }
+            Err(nom::Err::Incomplete(_)) => {
+                return Err(format!("line {}: incomplete value", n + 1))
+            }
+        }
+    }
+    Ok(JsonValue::Object(obj))
+}
+
+/// Recognise one value, with surrounding whitespace, without building it.
+pub(crate) fn skip_value<'a, E: ParseError<&'a str>>(
+    i: &'a str,
+    opts: &ParseOptions,
+) -> IResult<&'a str, (), E> {
+    let member = map(
+        separated_pair(
+            |i| key(i, opts),
+            cut(tag(":")),
+            cut(|i| skip_value(i, opts)),
+        ),
+        |_| (),
+    );
+    let skip_object = context(
+        "object",
+        delimited(
+            tag("{"),
+            separated_list_with_capacity(0, tag(","), member),
+            preceded(multispace0, cut(char('}'))),
+        ),
+    );
+    let skip_array = context(
+        "array",
+        delimited(
+            tag("["),
+            separated_list_with_capacity(0, tag(","), |i| skip_value(i, opts)),
+            preceded(multispace0, cut(char(']'))),
+        ),
+    );
     delimited(
         multispace0,
-        alt((map(object, JsonValue::Object), map(array, JsonValue::Array))),
+        alt((
+            n_value((), skip_object),
+            n_value((), skip_array),
+            n_value((), |i| string_with(i, opts)),
+            n_value((), |i| number(i, opts)),
+            n_value((), boolean),
+            n_value((), null),
+            n_value((), |i| undefined(i, opts)),
+        )),
+        multispace0,
+    )(i)
+}
+
+/// Run `member` over a comma-separated list like
+/// `separated_list_with_capacity`, including failing on a bad member after
+/// a comma. `member` yields `Some` for the members on the pointer's path;
+/// the last of those wins, as a repeated key does when parsing normally.
+fn last_match<'a, E, F>(mut i: &'a str, mut member: F) -> IResult<&'a str, Option<JsonValue>, E>
+where
+    E: ParseError<&'a str>,
+    F: FnMut(&'a str) -> IResult<&'a str, Option<Option<JsonValue>>, E>,
+{
+    let mut found = None;
+    let mut start = i;
+    loop {
+        match member(start) {
+            Err(nom::Err::Error(_)) if start == i => return Ok((i, found)),
+            Err(nom::Err::Error(e)) => return Err(nom::Err::Failure(e)),
+            Err(e) => return Err(e),
+            Ok((rest, v)) => {
+                if let Some(v) = v {
+                    found = v;
+                }
+                i = rest;
+            }
+        }
+        start = match tag::<_, _, E>(",")(i) {
+            Err(nom::Err::Error(_)) => return Ok((i, found)),
+            Err(e) => return Err(e),
+            Ok((rest, _)) => rest,
+        };
+    }
+}
+
+fn find_in_object<'a, E: ParseError<&'a str>>(
+    i: &'a str,
+    token: &str,
+    tokens: &[String],
+    opts: &ParseOptions,
+) -> IResult<&'a str, Option<JsonValue>, E> {
+    let (i, _) = tag("{")(i)?;
+    let (i, found) = last_match(i, |i| {
+        let (i, k) = terminated(|i| key(i, opts), cut(tag(":")))(i)?;
+        if k == token {
+            cut(map(|i| find(i, tokens, opts), Some))(i)
+        } else {
+            cut(map(|i| skip_value(i, opts), |_| None))(i)
+        }
+    })?;
+    let (i, _) = preceded(multispace0, cut(char('}')))(i)?;
+    Ok((i, found))
+}
+
+fn find_in_array<'a, E: ParseError<&'a str>>(
+    i: &'a str,
+    token: &str,
+    tokens: &[String],
+    opts: &ParseOptions,
+) -> IResult<&'a str, Option<JsonValue>, E> {
+    let target = pointer::index(token);
+    let mut n = 0;
+    let (i, _) = tag("[")(i)?;
+    let (i, found) = last_match(i, |i| {
+        n += 1;
+        if target == Some(n - 1) {
+            map(|i| find(i, tokens, opts), Some)(i)
+        } else {
+            map(|i| skip_value(i, opts), |_| None)(i)
+        }
+    })?;
+    let (i, _) = preceded(multispace0, cut(char(']')))(i)?;
+    Ok((i, found))
+}
+
+/// Parse the value at the start of `i` like `value`, but only build the
+/// part reached by following `tokens`; everything else is skipped.
+fn find<'a, E: ParseError<&'a str>>(
+    i: &'a str,
+    tokens: &[String],
+    opts: &ParseOptions,
+) -> IResult<&'a str, Option<JsonValue>, E> {
+    let (token, tokens) = match tokens.split_first() {
+        Some(split) => split,
+        None => return map(|i| value(i, opts), Some)(i),
+    };
+    let (i, _) = multispace0(i)?;
+    let (i, found) = if i.starts_with('{') {
+        context("object", |i| find_in_object(i, token, tokens, opts))(i)?
+    } else if i.starts_with('[') {
+        context("array", |i| find_in_array(i, token, tokens, opts))(i)?
+    } else {
+        map(|i| skip_value(i, opts), |_| None)(i)?
+    };
+    let (i, _) = multispace0(i)?;
+    Ok((i, found))
+}
+
+/// `find` over a top-level object or array, after an optional byte order
+/// mark.
+fn find_document<'a, E: ParseError<&'a str>>(
+    input: &'a str,
+    tokens: &[String],
+    opts: &ParseOptions,
+) -> IResult<&'a str, Option<JsonValue>, E> {
+    preceded(
+        preceded(opt(char('\u{feff}')), multispace0),
+        preceded(peek(alt((char('{'), char('[')))), |i| find(i, tokens, opts)),
+    )(input)
+}
+
+/// Extract the value at JSON Pointer `path` from a document without
+/// building the rest of the tree.
+///
+/// The whole document is still scanned, so it must be valid, and a
+/// repeated key resolves to its last value exactly as with `root` and
+/// `JsonValue::pointer`. `Ok(None)` if nothing is at `path`; input after
+/// the document is ignored.
+pub fn parse_field(input: &str, path: &str) -> Result<Option<JsonValue>, String> {
+    let tokens = match pointer::tokens(path) {
+        Some(tokens) => tokens,
+        None => return Err(format!("invalid JSON Pointer `{}`", path)),
+    };
+    let opts = ParseOptions::default();
+    // cheap errors while skipping; parse again only to describe a failure
+    match find_document::<(&str, ErrorKind)>(input, &tokens, &opts) {
+        Ok((_, found)) => return Ok(found),
+        Err(nom::Err::Incomplete(_)) => return Err(String::from("incomplete input")),
+        Err(_) => {}
+    }
+    match find_document::<VerboseError<&str>>(input, &tokens, &opts) {
+        Ok((_, found)) => Ok(found),
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Err(describe_error(input, e)),
+        Err(nom::Err::Incomplete(_)) => Err(String::from("incomplete input")),
+    }
+}
+
+/// `true` if the whole of `input` is valid JSON per RFC 8259, with
+/// nothing but whitespace after it. Like `root`, the top level must be an
+/// object or array, and a leading byte order mark is skipped. Nothing is
+/// built while checking.
+pub fn is_valid_json(input: &str) -> bool {
+    let opts = ParseOptions::default();
+    let parsed = preceded(
+        preceded(opt(char('\u{feff}')), multispace0),
+        preceded(peek(alt((char('{'), char('[')))), |i| {
+            skip_value::<(&str, ErrorKind)>(i, &opts)
+        }),
+    )(input);
+    matches!(parsed, Ok(("", ())))
+}
+
+/// A top-level object or array. One byte order mark is skipped if it is the
+/// very first character; U+FEFF anywhere else is ordinary text.
+pub(crate) fn document<'a, E: ParseError<&'a str>>(
+    i: &'a str,
+    opts: &ParseOptions,
+) -> IResult<&'a str, JsonValue, E> {
+    delimited(
+        preceded(opt(char('\u{feff}')), multispace0),
+        alt((
+            map(
+                |i| object(i, opts, opts.initial_capacity_hint),
+                JsonValue::Object,
+            ),
+            map(
+                |i| array(i, opts, opts.initial_capacity_hint),
+                JsonValue::Array,
+            ),
+        )),
         multispace0,
     )(i)
 }
 
+pub(crate) fn describe_error(input: &str, e: VerboseError<&str>) -> String {
+    let mismatch = match e.errors.first() {
+        Some((at, VerboseErrorKind::Char(close))) if *close == ']' || *close == '}' => {
+            match at.chars().next() {
+                Some(c @ ']') | Some(c @ '}') => Some((close, format!("`{}`", c))),
+                Some(_) => None,
+                None => Some((close, String::from("end of input"))),
+            }
+        }
+        _ => None,
+    };
+    if let Some((close, found)) = mismatch {
+        let opener = e.errors.iter().find_map(|(pos, kind)| match kind {
+            VerboseErrorKind::Context(ctx) if *ctx == "array" || *ctx == "object" => {
+                Some((pos, ctx))
+            }
+            _ => None,
+        });
+        if let Some((pos, ctx)) = opener {
+            return format!(
+                "expected `{}` to close {} opened at offset {}, found {}",
+                close,
+                ctx,
+                input.len() - pos.len(),
+                found
+            );
+        }
+    }
+    convert_error(input, e)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use nom::{error::ErrorKind, Err};
 
+    type BasicError<'a> = (&'a str, ErrorKind);
+
     #[test]
     fn test_parse_string() {
-        assert_eq!(string(r#""hello""#), Ok(("", "hello")));
-        assert_eq!(string(r#""he\rllo""#), Ok(("", r"he\rllo")));
-        assert_eq!(string(r#""he\tllo""#), Ok(("", r"he\tllo")));
-        assert_eq!(string(r#""he\u1234""#), Ok(("", r"he\u1234")));
-        assert_eq!(string(r#""""#), Ok(("", r#""""#)));
+        assert_eq!(string::<BasicError>(r#""hello""#), Ok(("", "hello")));
+        assert_eq!(string::<BasicError>(r#""he\rllo""#), Ok(("", r"he\rllo")));
+        assert_eq!(string::<BasicError>(r#""he\tllo""#), Ok(("", r"he\tllo")));
+        assert_eq!(string::<BasicError>(r#""he\u1234""#), Ok(("", r"he\u1234")));
+        assert_eq!(string::<BasicError>(r#""""#), Ok(("", r#""""#)));
     }
 
     #[test]
     fn test_array() {
         let v: Vec<JsonValue> = vec![];
-        assert_eq!(array("[]"), Ok(("", v)));
         assert_eq!(
-            array(r#"["abc"   , null, true,  false]"#),
+            array::<BasicError>("[]", &ParseOptions::default(), 0),
+            Ok(("", v))
+        );
+        assert_eq!(
+            array::<BasicError>(
+                r#"["abc"   , null, true,  false]"#,
+                &ParseOptions::default(),
+                0
+            ),
             Ok((
                 "",
                 vec![
@@ -170,7 +803,10 @@ mod test {
 
     #[test]
     fn test_object() {
-        assert_eq!(object(r#"{}"#), Ok(("", HashMap::new())));
+        assert_eq!(
+            object::<BasicError>(r#"{}"#, &ParseOptions::default(), 0),
+            Ok(("", HashMap::new()))
+        );
         let mut hash = HashMap::new();
         hash.insert(String::from("key"), JsonValue::Str(String::from("val")));
         hash.insert(
@@ -182,25 +818,38 @@ mod test {
             ]),
         );
         assert_eq!(
-            object(r#"{"key": "val"  , "arr" :    [true, false, null]}"#),
+            object::<BasicError>(
+                r#"{"key": "val"  , "arr" :    [true, false, null]}"#,
+                &ParseOptions::default(),
+                0
+            ),
             Ok(("", hash))
         );
     }
 
     #[test]
     fn test_value() {
-        assert_eq!(value("true"), Ok(("", JsonValue::Boolean(true))));
-        assert_eq!(value("false"), Ok(("", JsonValue::Boolean(false))));
-        assert_eq!(value("null"), Ok(("", JsonValue::Null)));
         assert_eq!(
-            value(r#""\b\\\"\f\n\r\n\t\u1234""#),
+            value::<BasicError>("true", &ParseOptions::default()),
+            Ok(("", JsonValue::Boolean(true)))
+        );
+        assert_eq!(
+            value::<BasicError>("false", &ParseOptions::default()),
+            Ok(("", JsonValue::Boolean(false)))
+        );
+        assert_eq!(
+            value::<BasicError>("null", &ParseOptions::default()),
+            Ok(("", JsonValue::Null))
+        );
+        assert_eq!(
+            value::<BasicError>(r#""\b\\\"\f\n\r\n\t\u1234""#, &ParseOptions::default()),
             Ok((
                 "",
-                JsonValue::Str(String::from("\\b\\\\\\\"\\f\\n\\r\\n\\t\\u1234"))
+                JsonValue::Str(String::from("\u{8}\\\"\u{c}\n\r\n\t\u{1234}"))
             ))
         );
         assert_eq!(
-            value(r#"["abc", true, false, null]"#),
+            value::<BasicError>(r#"["abc", true, false, null]"#, &ParseOptions::default()),
             Ok((
                 "",
                 JsonValue::Array(vec![
@@ -218,31 +867,501 @@ mod test {
         .into_iter()
         .collect();
         assert_eq!(
-            value(r#"{"key": "val", "arr": []}"#),
+            value::<BasicError>(r#"{"key": "val", "arr": []}"#, &ParseOptions::default()),
             Ok(("", JsonValue::Object(hashmap)))
         )
     }
 
     #[test]
     fn test_parse_str() {
-        assert_eq!(parse_str(r#"\u1234"#), Ok(("", r#"\u1234"#)));
+        assert_eq!(parse_str::<BasicError>(r#"\u1234"#), Ok(("", r#"\u1234"#)));
         assert_eq!(
-            parse_str(r#"\b\\\"\f\n\r\n\t\u1234"#),
+            parse_str::<BasicError>(r#"\b\\\"\f\n\r\n\t\u1234"#),
             Ok(("", r#"\b\\\"\f\n\r\n\t\u1234"#))
         );
         assert_eq!(
-            parse_str(r#""abcd"#),
+            parse_str::<BasicError>(r#""abcd"#),
             Err(Err::Error(("\"abcd", ErrorKind::Escaped)))
         );
     }
 
     #[test]
     fn test_parse_hex() {
-        assert_eq!(parse_hex(r#"u1234"#), Ok(("", r#"u1234"#)));
-        assert_eq!(parse_hex(r#"u12346"#), Ok(("6", r#"u1234"#)));
+        assert_eq!(parse_hex::<BasicError>(r#"u1234"#), Ok(("", r#"1234"#)));
+        assert_eq!(parse_hex::<BasicError>(r#"u12346"#), Ok(("6", r#"1234"#)));
+        assert_eq!(
+            parse_hex::<BasicError>(r#"u1g34"#),
+            Err(Err::Error((r#"1g34"#, ErrorKind::TakeWhileMN)))
+        );
         assert_eq!(
-            parse_hex(r#"u1g34"#),
-            Err(Err::Error((r#"u1g34"#, ErrorKind::TakeWhileMN)))
+            parse_hex::<BasicError>(r#"uuuuu"#),
+            Err(Err::Error((r#"uuuu"#, ErrorKind::TakeWhileMN)))
         );
     }
+
+    fn snake_case(k: &str) -> String {
+        let mut out = String::new();
+        for c in k.chars() {
+            if c.is_ascii_uppercase() {
+                out.push('_');
+                out.push(c.to_ascii_lowercase());
+            } else {
+                out.push(c);
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_key_transform() {
+        let opts = ParseOptions {
+            key_transform: Some(snake_case),
+            ..ParseOptions::default()
+        };
+        let inner = vec![(String::from("first_name"), JsonValue::Num(1.0))]
+            .into_iter()
+            .collect();
+        let item = vec![(String::from("item_id"), JsonValue::Null)]
+            .into_iter()
+            .collect();
+        let hashmap = vec![
+            (String::from("user_name"), JsonValue::Object(inner)),
+            (
+                String::from("item_list"),
+                JsonValue::Array(vec![JsonValue::Object(item)]),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            root_with(
+                r#"{"userName": {"firstName": 1}, "itemList": [{"itemId": null}]}"#,
+                &opts
+            ),
+            Ok(("", JsonValue::Object(hashmap)))
+        );
+    }
+
+    #[test]
+    fn test_bracket_mismatch() {
+        let opts = ParseOptions::default();
+        assert_eq!(
+            root_verbose("[1, 2}", &opts),
+            Err(String::from(
+                "expected `]` to close array opened at offset 0, found `}`"
+            ))
+        );
+        assert_eq!(
+            root_verbose(r#"{"a": [1, {"b": 2]]}"#, &opts),
+            Err(String::from(
+                "expected `}` to close object opened at offset 10, found `]`"
+            ))
+        );
+        assert_eq!(
+            root_verbose(r#"{"a": [1, 2"#, &opts),
+            Err(String::from(
+                "expected `]` to close array opened at offset 6, found end of input"
+            ))
+        );
+        for input in &[r#"{ 1"a": 2}"#, "[1, tru]", r#"{"a": tru}"#, "[1, 2,]"] {
+            match root_verbose(input, &opts) {
+                Err(msg) => assert!(!msg.contains("to close"), "{}: {}", input, msg),
+                other => panic!("{}: unexpected {:?}", input, other),
+            }
+        }
+        assert_eq!(
+            root_verbose("[1, [ ]]", &opts),
+            Ok((
+                "",
+                JsonValue::Array(vec![JsonValue::Num(1.0), JsonValue::Array(vec![])])
+            ))
+        );
+    }
+
+    #[test]
+    fn test_object_member_errors() {
+        let opts = ParseOptions::default();
+        assert_eq!(
+            object::<BasicError>(r#"{"a" 1}"#, &opts, 0),
+            Err(Err::Failure(("1}", ErrorKind::Tag)))
+        );
+        assert!(matches!(
+            object::<BasicError>(r#"{"a": }"#, &opts, 0),
+            Err(Err::Failure(("}", _)))
+        ));
+    }
+
+    #[test]
+    fn test_reject_imprecise_integers() {
+        let strict = ParseOptions {
+            reject_imprecise_integers: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            root_with("[9007199254740993]", &ParseOptions::default()),
+            Ok((
+                "",
+                JsonValue::Array(vec![JsonValue::Num(9007199254740992.0)])
+            ))
+        );
+        assert_eq!(
+            root_with("[9007199254740993]", &strict),
+            Err(Err::Failure(("9007199254740993]", ErrorKind::Verify)))
+        );
+        assert_eq!(
+            root_with("[9223372036854775807]", &strict),
+            Err(Err::Failure(("9223372036854775807]", ErrorKind::Verify)))
+        );
+        assert_eq!(
+            root_with("[-9223372036854775807]", &strict),
+            Err(Err::Failure(("-9223372036854775807]", ErrorKind::Verify)))
+        );
+        match root_verbose("[9007199254740993]", &strict) {
+            Err(msg) => assert!(
+                msg.contains("integer literal is not exactly representable as f64"),
+                "{}",
+                msg
+            ),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(
+            root_with("[99999999999999999999]", &strict),
+            Err(Err::Failure(("99999999999999999999]", ErrorKind::Verify)))
+        );
+        assert_eq!(
+            root_with(
+                "[9007199254740992, -42, 1.5, 1e300, -9223372036854775808]",
+                &strict
+            ),
+            Ok((
+                "",
+                JsonValue::Array(vec![
+                    JsonValue::Num(9007199254740992.0),
+                    JsonValue::Num(-42.0),
+                    JsonValue::Num(1.5),
+                    JsonValue::Num(1e300),
+                    JsonValue::Num(-9223372036854775808.0)
+                ])
+            ))
+        );
+    }
+
+    fn tag_dates(s: &str) -> Option<JsonValue> {
+        let is_date = s.len() == 10
+            && s.char_indices().all(|(i, c)| match i {
+                4 | 7 => c == '-',
+                _ => c.is_ascii_digit(),
+            });
+        if is_date {
+            Some(JsonValue::object_from_pairs(vec![(
+                String::from("$date"),
+                JsonValue::Str(String::from(s)),
+            )]))
+        } else {
+            None
+        }
+    }
+
+    #[test]
+    fn test_scalar_hook() {
+        let opts = ParseOptions {
+            scalar_hook: Some(tag_dates),
+            ..ParseOptions::default()
+        };
+        let date = JsonValue::object_from_pairs(vec![(
+            String::from("$date"),
+            JsonValue::Str(String::from("2020-05-17")),
+        )]);
+        let expected = JsonValue::object_from_pairs(vec![
+            (String::from("2020-01-01"), JsonValue::Array(vec![date])),
+            (
+                String::from("name"),
+                JsonValue::Str(String::from("2020-5-17")),
+            ),
+        ]);
+        assert_eq!(
+            root_with(
+                r#"{"2020-01-01": ["2020-05-17"], "name": "2020-5-17"}"#,
+                &opts
+            ),
+            Ok(("", expected))
+        );
+    }
+
+    #[test]
+    fn test_debug_sorted_keys() {
+        let (_, a) = root(r#"{"b": [1, null], "a": {"y": true, "x": "s"}, "c": 2}"#).unwrap();
+        let (_, b) = root(r#"{"c": 2, "a": {"x": "s", "y": true}, "b": [1, null]}"#).unwrap();
+        let expected = r#"Object({"a": Object({"x": Str("s"), "y": Boolean(true)}), "b": Array([Num(1.0), Null]), "c": Num(2.0)})"#;
+        assert_eq!(format!("{:?}", a), expected);
+        assert_eq!(format!("{:?}", b), expected);
+        assert_eq!(format!("{:#?}", a), format!("{:#?}", b));
+    }
+
+    #[test]
+    fn test_parse_keyvalue_lines() {
+        let input = "name: \"demo\"\n\n  port : 8080\ndebug: true\nurl: \"http://x:1\"\ntags: [\"a\", null]\n";
+        let (_, expected) = root(
+            r#"{"name": "demo", "port": 8080, "debug": true, "url": "http://x:1", "tags": ["a", null]}"#,
+        )
+        .unwrap();
+        assert_eq!(parse_keyvalue_lines(input), Ok(expected));
+        assert_eq!(
+            parse_keyvalue_lines(""),
+            Ok(JsonValue::Object(HashMap::new()))
+        );
+
+        assert_eq!(
+            parse_keyvalue_lines("a: 1\nb 2"),
+            Err(String::from("line 2: expected `key: value`"))
+        );
+        assert_eq!(
+            parse_keyvalue_lines(": 1"),
+            Err(String::from("line 1: missing key before `:`"))
+        );
+        assert_eq!(
+            parse_keyvalue_lines("a: 1 2"),
+            Err(String::from("line 1: unexpected `2`"))
+        );
+        assert_eq!(
+            parse_keyvalue_lines("a: [1"),
+            Err(String::from(
+                "line 1: expected `]` to close array opened at offset 1, found end of input"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_invalid_escape_policy() {
+        let input = r#"{"k\q": "a\qb\n\u12zz\u00e9"}"#;
+        let with = |invalid_escape_policy| ParseOptions {
+            invalid_escape_policy,
+            ..ParseOptions::default()
+        };
+        assert!(root_with(input, &with(InvalidEscapePolicy::Error)).is_err());
+        for bad in &[r#"["\uuuuu"]"#, r#"["\u12u4"]"#, r#"["\u12"]"#] {
+            assert!(root_with(bad, &with(InvalidEscapePolicy::Error)).is_err());
+            assert!(!is_valid_json(bad));
+        }
+        assert_eq!(
+            root_with(input, &with(InvalidEscapePolicy::Literal)),
+            Ok((
+                "",
+                JsonValue::object_from_pairs(vec![(
+                    String::from("kq"),
+                    JsonValue::Str(String::from("aqb\nu12zz\u{e9}"))
+                )])
+            ))
+        );
+        assert_eq!(
+            root_with(input, &with(InvalidEscapePolicy::Replace)),
+            Ok((
+                "",
+                JsonValue::object_from_pairs(vec![(
+                    String::from("k\u{fffd}"),
+                    JsonValue::Str(String::from("a\u{fffd}b\n\u{fffd}12zz\u{e9}"))
+                )])
+            ))
+        );
+    }
+
+    #[test]
+    fn test_byte_order_mark() {
+        let escaped = JsonValue::object_from_pairs(vec![(
+            String::from("a"),
+            JsonValue::Str(String::from("\u{feff}")),
+        )]);
+        assert_eq!(
+            root("\u{feff}{\"a\": \"\\uFEFF\"}"),
+            Ok(("", escaped.clone()))
+        );
+        assert_eq!(root(r#"{"a":"\uFEFF"}"#), Ok(("", escaped)));
+
+        let literal = JsonValue::Array(vec![JsonValue::Str(String::from("\u{feff}x"))]);
+        assert_eq!(root("\u{feff}[\"\u{feff}x\"]"), Ok(("", literal)));
+
+        assert!(root("\u{feff}\u{feff}[]").is_err());
+        assert!(root("[\u{feff}1]").is_err());
+    }
+
+    #[test]
+    fn test_initial_capacity_hint() {
+        let opts = ParseOptions {
+            initial_capacity_hint: 1000,
+            ..ParseOptions::default()
+        };
+        let input = format!("[1, [2, 3]{}]", ", 4".repeat(999));
+        match root_with(&input, &opts) {
+            Ok(("", JsonValue::Array(arr))) => {
+                assert!(arr.capacity() >= 1000);
+                match &arr[1] {
+                    JsonValue::Array(inner) => assert!(inner.capacity() < 1000),
+                    other => panic!("expected an array, got {:?}", other),
+                }
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        let few = ParseOptions {
+            initial_capacity_hint: 3,
+            ..ParseOptions::default()
+        };
+        match root_with(r#"{"a": 1, "b": 2}"#, &few) {
+            Ok(("", JsonValue::Object(obj))) => assert!(obj.capacity() >= 3),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(root_with("[1, 2, 3]", &opts), root("[1, 2, 3]"));
+
+        let huge = ParseOptions {
+            initial_capacity_hint: usize::MAX / 2,
+            ..ParseOptions::default()
+        };
+        assert_eq!(root_with("[1]", &huge), root("[1]"));
+        assert_eq!(root_with(r#"{"a": 1}"#, &huge), root(r#"{"a": 1}"#));
+    }
+
+    #[test]
+    fn test_allow_undefined() {
+        let input = r#"{"a": undefined, "b": [1, undefined]}"#;
+        let err = root_verbose(input, &ParseOptions::default()).unwrap_err();
+        assert!(err.contains("`undefined` is not valid JSON"), "{}", err);
+
+        let opts = ParseOptions {
+            allow_undefined: true,
+            ..ParseOptions::default()
+        };
+        let (_, expected) = root(r#"{"a": null, "b": [1, null]}"#).unwrap();
+        assert_eq!(root_with(input, &opts), Ok(("", expected)));
+        assert!(root_with("[undefinedx]", &opts).is_err());
+        assert!(root_with("[undefined1]", &opts).is_err());
+        assert!(root_with(r#"{"undefined": 1}"#, &ParseOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn test_parse_field() {
+        let input = r#" {"meta": {"n": 2, "skip": [1, {"x": "y\n"}, null]},
+            "items": [{"id": 1}, {"id": 2, "tags": ["a", "b"]}], "a/b": true, "meta": {"n": 3}} "#;
+        let (_, full) = root(input).unwrap();
+        for path in &[
+            "",
+            "/meta",
+            "/meta/n",
+            "/meta/skip",
+            "/items/1/tags/0",
+            "/items/1",
+            "/a~1b",
+            "/items/2",
+            "/items/01",
+            "/missing/x",
+            "/meta/n/x",
+        ] {
+            assert_eq!(
+                parse_field(input, path),
+                Ok(full.pointer(path).cloned()),
+                "{}",
+                path
+            );
+        }
+
+        assert!(parse_field(input, "items").is_err());
+        assert_eq!(
+            parse_field(r#"{"a": [1}, "b": 2}"#, "/b"),
+            Err(String::from(
+                "expected `]` to close array opened at offset 6, found `}`"
+            ))
+        );
+        assert!(parse_field(r#"{"a": 1, "b": tru}"#, "/a").is_err());
+        // the caret sits on the missing value, not on the comma before it
+        let msg = parse_field(r#"{"a": 1, "b": }"#, "/a").unwrap_err();
+        assert_eq!(msg.lines().nth(2), Some("              ^"), "{}", msg);
+        let msg = parse_field("[1, ]", "/0").unwrap_err();
+        assert_eq!(msg.lines().nth(2), Some("    ^"), "{}", msg);
+        assert!(parse_field("1", "").is_err());
+    }
+
+    #[test]
+    fn test_unescape() {
+        let opts = ParseOptions::default();
+        let decode = |s| value::<BasicError>(s, &opts).map(|(_, v)| v);
+        assert_eq!(decode(r#""\b""#), Ok(JsonValue::Str(String::from("\u{8}"))));
+        assert_eq!(decode(r#""\f""#), Ok(JsonValue::Str(String::from("\u{c}"))));
+        assert_eq!(
+            decode(r#""a\bb\fc\/\\b""#),
+            Ok(JsonValue::Str(String::from("a\u{8}b\u{c}c/\\b")))
+        );
+        assert_eq!(
+            decode(r#""\u0008\u000C😀""#),
+            Ok(JsonValue::Str(String::from("\u{8}\u{c}😀")))
+        );
+        assert_eq!(
+            decode(r#""\ud83d\ude00""#),
+            Ok(JsonValue::Str(String::from("\u{1f600}")))
+        );
+        assert_eq!(
+            decode(r#""\ud83d!\ude00""#),
+            Ok(JsonValue::Str(String::from("\u{fffd}!\u{fffd}")))
+        );
+        assert_eq!(
+            root(r#"{"\f": 1}"#).map(|(_, v)| v.get("\u{c}").cloned()),
+            Ok(Some(JsonValue::Num(1.0)))
+        );
+    }
+
+    #[test]
+    fn test_is_valid_json() {
+        for valid in &[
+            "{}",
+            " [1, 2.5, -3e2, true, false, null] ",
+            r#"{"a": {"b": ["c", {}]}}"#,
+            "\u{feff}[]",
+            "[0, -0, -0.5, 10, 1E+2, 2e-3]",
+        ] {
+            assert!(is_valid_json(valid), "{}", valid);
+        }
+        for invalid in &[
+            "",
+            "[1, 2",
+            "[1 2]",
+            r#"{"a" 1}"#,
+            "[1] [2]",
+            "[1] x",
+            "[tru]",
+            "42",
+            r#"["\q"]"#,
+            "[.5]",
+            "[1.]",
+            "[+1]",
+            "[01]",
+            "[1e]",
+            "[-]",
+            r#"["\uuuuu"]"#,
+        ] {
+            assert!(!is_valid_json(invalid), "{}", invalid);
+            assert!(root(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_trailing_data() {
+        let input = "[1, 2]  garbage";
+        let with = |trailing_data| ParseOptions {
+            trailing_data,
+            ..ParseOptions::default()
+        };
+        let arr = JsonValue::Array(vec![JsonValue::Num(1.0), JsonValue::Num(2.0)]);
+
+        assert_eq!(root(input), Err(Err::Failure(("garbage", ErrorKind::Eof))));
+        assert_eq!(
+            root_verbose(input, &with(TrailingData::Forbid)),
+            Err(String::from("unexpected trailing data at offset 8"))
+        );
+        assert_eq!(root(" [1, 2] \n"), Ok(("", arr.clone())));
+
+        let allow = with(TrailingData::Allow);
+        assert_eq!(root_with(input, &allow), Ok(("", arr.clone())));
+        assert_eq!(root_verbose(input, &allow), Ok(("", arr.clone())));
+
+        let ret = with(TrailingData::Return);
+        assert_eq!(root_with(input, &ret), Ok(("garbage", arr.clone())));
+        assert_eq!(root_verbose(input, &ret), Ok(("garbage", arr)));
+    }
 }
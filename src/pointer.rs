@@ -0,0 +1,356 @@
+use crate::parser::JsonValue;
+use std::{collections::HashMap, mem};
+
+/// Split an RFC 6901 JSON Pointer into unescaped reference tokens.
+/// `""` is the whole document; anything else must start with `/`.
+pub(crate) fn tokens(path: &str) -> Option<Vec<String>> {
+    if path.is_empty() {
+        return Some(Vec::new());
+    }
+    if !path.starts_with('/') {
+        return None;
+    }
+    Some(
+        path[1..]
+            .split('/')
+            .map(|t| t.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+    )
+}
+
+/// Append `token` to the pointer `path`, escaping `~` and `/`.
+pub(crate) fn push_token(path: &mut String, token: &str) {
+    path.push('/');
+    path.push_str(&token.replace('~', "~0").replace('/', "~1"));
+}
+
+/// Array index token: `0` or digits without a leading zero.
+pub(crate) fn index(token: &str) -> Option<usize> {
+    if token.is_empty() || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+    if !token.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+/// A subtree viewed as a document of its own, see `JsonValue::sub`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubValue<'a> {
+    value: &'a JsonValue,
+    path: String,
+}
+
+impl<'a> SubValue<'a> {
+    /// The subtree itself.
+    pub fn value(&self) -> &'a JsonValue {
+        self.value
+    }
+
+    /// Pointer of the subtree from the original root.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Like `JsonValue::pointer`, relative to the subtree.
+    pub fn pointer(&self, path: &str) -> Option<&'a JsonValue> {
+        self.value.pointer(path)
+    }
+
+    /// A narrower view; `path` is relative to this one.
+    pub fn sub(&self, path: &str) -> Option<SubValue<'a>> {
+        Some(SubValue {
+            value: self.pointer(path)?,
+            path: format!("{}{}", self.path, path),
+        })
+    }
+}
+
+/// Paths picked by `project`, merged into a tree of reference tokens.
+#[derive(Default)]
+struct Selection {
+    whole: bool,
+    children: HashMap<String, Selection>,
+}
+
+impl Selection {
+    fn insert(&mut self, tokens: Vec<String>) {
+        let node = tokens
+            .into_iter()
+            .fold(self, |node, token| node.children.entry(token).or_default());
+        node.whole = true;
+    }
+
+    fn apply(&self, v: &JsonValue) -> JsonValue {
+        if self.whole {
+            return v.clone();
+        }
+        match v {
+            JsonValue::Object(obj) => JsonValue::Object(
+                obj.iter()
+                    .filter_map(|(k, item)| Some((k.clone(), self.children.get(k)?.apply(item))))
+                    .collect(),
+            ),
+            JsonValue::Array(arr) => JsonValue::Array(
+                arr.iter()
+                    .enumerate()
+                    .filter_map(|(i, item)| Some(self.children.get(&i.to_string())?.apply(item)))
+                    .collect(),
+            ),
+            _ => JsonValue::Null,
+        }
+    }
+}
+
+impl JsonValue {
+    fn child(&self, token: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(obj) => obj.get(token),
+            JsonValue::Array(arr) => arr.get(index(token)?),
+            _ => None,
+        }
+    }
+
+    fn child_mut(&mut self, token: &str) -> Option<&mut JsonValue> {
+        match self {
+            JsonValue::Object(obj) => obj.get_mut(token),
+            JsonValue::Array(arr) => arr.get_mut(index(token)?),
+            _ => None,
+        }
+    }
+
+    /// Look up a value by JSON Pointer, e.g. `/items/0/name`.
+    pub fn pointer(&self, path: &str) -> Option<&JsonValue> {
+        tokens(path)?
+            .iter()
+            .try_fold(self, |v, token| v.child(token))
+    }
+
+    /// View of the value at `path` in which further pointers are relative
+    /// to it, so `v.sub("/a")?.pointer("/b")` is `v.pointer("/a/b")`.
+    pub fn sub(&self, path: &str) -> Option<SubValue<'_>> {
+        Some(SubValue {
+            value: self.pointer(path)?,
+            path: String::from(path),
+        })
+    }
+
+    /// Mutable counterpart of `pointer`.
+    pub fn pointer_mut(&mut self, path: &str) -> Option<&mut JsonValue> {
+        tokens(path)?
+            .iter()
+            .try_fold(self, |v, token| v.child_mut(token))
+    }
+
+    /// Put `new` in place of the value at `path` and return the old one.
+    /// `None`, with the document untouched, if nothing is at `path`; use
+    /// `""` to replace the whole document.
+    pub fn replace_pointer(&mut self, path: &str, new: JsonValue) -> Option<JsonValue> {
+        self.pointer_mut(path).map(|slot| mem::replace(slot, new))
+    }
+
+    /// Remove the value at `path` from its parent and return it.
+    ///
+    /// Removing from an array shifts the later elements down, as in
+    /// RFC 6902 `remove`. `None` if the path does not exist; the whole
+    /// document (`""`) cannot be removed.
+    pub fn remove_pointer(&mut self, path: &str) -> Option<JsonValue> {
+        let mut tokens = tokens(path)?;
+        let last = tokens.pop()?;
+        let parent = tokens
+            .iter()
+            .try_fold(self, |v, token| v.child_mut(token))?;
+        match parent {
+            JsonValue::Object(obj) => obj.remove(&last),
+            JsonValue::Array(arr) => {
+                let i = index(&last)?;
+                if i < arr.len() {
+                    Some(arr.remove(i))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// First node, this one included, satisfying `predicate`, with its
+    /// JSON Pointer. Depth-first, parents before children; array elements
+    /// are visited in order and object members in sorted key order, so the
+    /// result does not depend on hash order. Stops at the first match.
+    pub fn find_first<P: Fn(&JsonValue) -> bool>(
+        &self,
+        predicate: P,
+    ) -> Option<(String, &JsonValue)> {
+        let mut path = String::new();
+        let found = self.find_from(&predicate, &mut path)?;
+        Some((path, found))
+    }
+
+    fn find_from<P: Fn(&JsonValue) -> bool>(
+        &self,
+        predicate: &P,
+        path: &mut String,
+    ) -> Option<&JsonValue> {
+        if predicate(self) {
+            return Some(self);
+        }
+        let base = path.len();
+        match self {
+            JsonValue::Array(arr) => {
+                for (i, item) in arr.iter().enumerate() {
+                    push_token(path, &i.to_string());
+                    if let Some(found) = item.find_from(predicate, path) {
+                        return Some(found);
+                    }
+                    path.truncate(base);
+                }
+            }
+            JsonValue::Object(obj) => {
+                let mut keys: Vec<&String> = obj.keys().collect();
+                keys.sort();
+                for k in keys {
+                    push_token(path, k);
+                    if let Some(found) = obj[k].find_from(predicate, path) {
+                        return Some(found);
+                    }
+                    path.truncate(base);
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Copy of this value holding only the nodes at `pointers`, plus the
+    /// objects and arrays leading to them.
+    ///
+    /// Pointers that do not resolve are skipped. Selected array elements
+    /// keep their relative order but are packed together, so their indices
+    /// may change. If nothing is selected the result is an empty object or
+    /// array (or `null` for a scalar).
+    pub fn project(&self, pointers: &[&str]) -> JsonValue {
+        let mut selection = Selection::default();
+        for path in pointers {
+            if self.pointer(path).is_some() {
+                selection.insert(tokens(path).unwrap());
+            }
+        }
+        selection.apply(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::root;
+
+    #[test]
+    fn test_pointer() {
+        let (_, v) = root(r#"{"a": [10, {"b/c": 1, "d~e": 2}]}"#).unwrap();
+        assert_eq!(v.pointer(""), Some(&v));
+        assert_eq!(v.pointer("/a/0"), Some(&JsonValue::Num(10.0)));
+        assert_eq!(v.pointer("/a/1/b~1c"), Some(&JsonValue::Num(1.0)));
+        assert_eq!(v.pointer("/a/1/d~0e"), Some(&JsonValue::Num(2.0)));
+        assert_eq!(v.pointer("/"), None);
+        assert_eq!(v.pointer("/a/01"), None);
+        assert_eq!(v.pointer("/a/2"), None);
+        assert_eq!(v.pointer("a"), None);
+    }
+
+    #[test]
+    fn test_sub() {
+        let (_, v) = root(r#"{"a": {"b": [10, {"c": true}]}, "x": 1}"#).unwrap();
+        let a = v.sub("/a").unwrap();
+        assert_eq!(a.value(), v.pointer("/a").unwrap());
+        assert_eq!(a.pointer("/b"), v.pointer("/a/b"));
+        assert_eq!(a.pointer("/b/1/c"), Some(&JsonValue::Boolean(true)));
+        assert_eq!(a.pointer("/x"), None);
+        assert_eq!(a.pointer(""), Some(a.value()));
+
+        let c = a.sub("/b/1").unwrap();
+        assert_eq!(c.path(), "/a/b/1");
+        assert_eq!(c.pointer("/c"), v.pointer("/a/b/1/c"));
+        assert_eq!(v.sub("").unwrap().value(), &v);
+        assert!(v.sub("/missing").is_none());
+        assert!(a.sub("/c").is_none());
+    }
+
+    #[test]
+    fn test_replace_pointer() {
+        let (_, mut v) = root(r#"{"a": {"b": [1, 2]}, "c": {"d": true}}"#).unwrap();
+        assert_eq!(
+            v.replace_pointer("/a/b/1", JsonValue::Str(String::from("x"))),
+            Some(JsonValue::Num(2.0))
+        );
+        let (_, d) = root(r#"{"d": true}"#).unwrap();
+        assert_eq!(v.replace_pointer("/c", JsonValue::Null), Some(d));
+        let (_, expected) = root(r#"{"a": {"b": [1, "x"]}, "c": null}"#).unwrap();
+        assert_eq!(v, expected);
+
+        assert_eq!(v.replace_pointer("/a/b/2", JsonValue::Null), None);
+        assert_eq!(v.replace_pointer("/missing", JsonValue::Null), None);
+        assert_eq!(v, expected);
+
+        assert_eq!(v.replace_pointer("", JsonValue::Null), Some(expected));
+        assert_eq!(v, JsonValue::Null);
+    }
+
+    #[test]
+    fn test_remove_pointer() {
+        let (_, mut v) = root(r#"{"user": {"name": "x", "age": 3}, "items": [1, 2, 3]}"#).unwrap();
+        assert_eq!(
+            v.remove_pointer("/user/name"),
+            Some(JsonValue::Str(String::from("x")))
+        );
+        assert_eq!(v.remove_pointer("/items/0"), Some(JsonValue::Num(1.0)));
+        assert_eq!(v.remove_pointer("/items/5"), None);
+        assert_eq!(v.remove_pointer("/user/missing"), None);
+        assert_eq!(v.remove_pointer("/nope/x"), None);
+        assert_eq!(v.remove_pointer(""), None);
+        let (_, expected) = root(r#"{"user": {"age": 3}, "items": [2, 3]}"#).unwrap();
+        assert_eq!(v, expected);
+    }
+
+    #[test]
+    fn test_find_first() {
+        let (_, v) = root(
+            r#"{"results": [{"ok": true}, {"error": {"code": 1}}, {"error": {"code": 2}}],
+                "a/b": {"error": {"code": 0}}}"#,
+        )
+        .unwrap();
+        let is_error = |v: &JsonValue| v.get("code").is_some();
+        let (path, found) = v.find_first(is_error).unwrap();
+        assert_eq!(path, "/a~1b/error");
+        assert_eq!(v.pointer(&path), Some(found));
+
+        let results = v.get("results").unwrap();
+        let (path, found) = results.find_first(is_error).unwrap();
+        assert_eq!(path, "/1/error");
+        assert_eq!(found.get_f64("code"), Some(1.0));
+
+        assert_eq!(v.find_first(|_| true), Some((String::new(), &v)));
+        assert_eq!(v.find_first(|v| *v == JsonValue::Null), None);
+    }
+
+    #[test]
+    fn test_project() {
+        let (_, v) = root(
+            r#"{"id": 7, "user": {"name": "x", "email": "e", "tags": ["a", "b", "c"]}, "debug": true}"#,
+        )
+        .unwrap();
+        let (_, expected) = root(r#"{"id": 7, "user": {"name": "x"}}"#).unwrap();
+        assert_eq!(v.project(&["/id", "/user/name", "/missing/x"]), expected);
+
+        let (_, expected) = root(r#"{"user": {"tags": ["a", "c"]}}"#).unwrap();
+        assert_eq!(v.project(&["/user/tags/2", "/user/tags/0"]), expected);
+
+        assert_eq!(
+            v.project(&["/user", "/user/name"]).get("user"),
+            v.get("user")
+        );
+        assert_eq!(v.project(&[""]), v);
+        assert_eq!(v.project(&[]), JsonValue::Object(HashMap::new()));
+    }
+}
@@ -0,0 +1,683 @@
+use crate::parser::JsonValue;
+use std::{
+    env,
+    ffi::OsString,
+    fmt::{self, Write},
+};
+
+/// Which characters the serializer escapes inside strings.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum EscapePolicy {
+    /// Only what JSON requires: `"`, `\` and control characters. All other
+    /// Unicode is written raw as UTF-8.
+    #[default]
+    Minimal,
+    /// Like `Minimal`, and everything outside ASCII is written as `\uXXXX`
+    /// (with surrogate pairs above the BMP).
+    AsciiOnly,
+    /// Like `Minimal`, and `<`, `>`, `&`, `'`, U+2028 and U+2029 are written
+    /// as `\uXXXX` so the output can be embedded in HTML `<script>` tags.
+    HtmlSafe,
+}
+
+/// Knobs that change how a `JsonValue` is written out.
+///
+/// `SerializeOptions::default()` gives the same output as `Display`.
+#[derive(Debug, Clone)]
+pub struct SerializeOptions {
+    /// How strings are escaped. Defaults to `EscapePolicy::Minimal`.
+    pub escape: EscapePolicy,
+    /// When set, output is spread over several lines: a container that
+    /// fits in the remaining width is written on one line, otherwise each
+    /// member goes on its own line, indented by `indent_str` per level.
+    /// `None` (the default) writes everything compactly on one line.
+    pub max_line_width: Option<usize>,
+    /// Indentation unit for multi-line output, repeated once per nesting
+    /// level, e.g. `"\t"` or four spaces. Only used when `max_line_width`
+    /// is set; see `to_string_pretty_with`. Each of its characters counts
+    /// as one column against `max_line_width`. It is written as is, so
+    /// anything but JSON whitespace (space, tab, `\n`, `\r`) makes the
+    /// output invalid JSON. Defaults to two spaces.
+    pub indent_str: String,
+    /// In multi-line output, always keep arrays whose elements are all
+    /// scalars on one line, however wide; objects and arrays holding
+    /// containers are still expanded.
+    pub inline_scalar_arrays: bool,
+    /// Wrap keys and scalars in ANSI color codes for terminal display. The
+    /// codes do not count against `max_line_width`.
+    pub color: bool,
+    /// In multi-line output, put a comma after the last member of every
+    /// container that is spread over several lines, as JSON5 allows. The
+    /// result is not strict JSON, so this is off by default.
+    pub emit_trailing_commas: bool,
+}
+
+impl Default for SerializeOptions {
+    fn default() -> Self {
+        SerializeOptions {
+            escape: EscapePolicy::default(),
+            max_line_width: None,
+            indent_str: String::from("  "),
+            inline_scalar_arrays: false,
+            color: false,
+            emit_trailing_commas: false,
+        }
+    }
+}
+
+fn needs_unicode_escape(c: char, policy: EscapePolicy) -> bool {
+    match policy {
+        EscapePolicy::Minimal => false,
+        EscapePolicy::AsciiOnly => !c.is_ascii(),
+        EscapePolicy::HtmlSafe => matches!(c, '<' | '>' | '&' | '\'' | '\u{2028}' | '\u{2029}'),
+    }
+}
+
+fn write_str<W: Write>(w: &mut W, s: &str, opts: &SerializeOptions) -> fmt::Result {
+    w.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => w.write_str("\\\"")?,
+            '\\' => w.write_str("\\\\")?,
+            '\n' => w.write_str("\\n")?,
+            '\r' => w.write_str("\\r")?,
+            '\t' => w.write_str("\\t")?,
+            '\u{8}' => w.write_str("\\b")?,
+            '\u{c}' => w.write_str("\\f")?,
+            c if (c as u32) < 0x20 || needs_unicode_escape(c, opts.escape) => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units).iter() {
+                    write!(w, "\\u{:04x}", unit)?;
+                }
+            }
+            c => w.write_char(c)?,
+        }
+    }
+    w.write_char('"')
+}
+
+fn write_num<W: Write>(w: &mut W, n: f64) -> fmt::Result {
+    // JSON has no representation for NaN or infinities
+    if n.is_finite() {
+        write!(w, "{}", n)
+    } else {
+        w.write_str("null")
+    }
+}
+
+// ANSI SGR parameters, roughly following jq's defaults
+const KEY_COLOR: &str = "1;34";
+const STRING_COLOR: &str = "32";
+const NUMBER_COLOR: &str = "36";
+const BOOLEAN_COLOR: &str = "33";
+const NULL_COLOR: &str = "1;30";
+
+fn write_colored<W, F>(w: &mut W, color: &str, opts: &SerializeOptions, body: F) -> fmt::Result
+where
+    W: Write,
+    F: FnOnce(&mut W) -> fmt::Result,
+{
+    if !opts.color {
+        return body(w);
+    }
+    write!(w, "\x1b[{}m", color)?;
+    body(w)?;
+    w.write_str("\x1b[0m")
+}
+
+fn write_key<W: Write>(w: &mut W, k: &str, opts: &SerializeOptions) -> fmt::Result {
+    write_colored(w, KEY_COLOR, opts, |w| write_str(w, k, opts))
+}
+
+/// Columns `s` takes up on a terminal, skipping color escapes. Control
+/// characters never reach the output raw, so any ESC starts one.
+fn display_width(s: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in s.chars() {
+        if in_escape {
+            in_escape = c != 'm';
+        } else if c == '\x1b' {
+            in_escape = true;
+        } else {
+            width += 1;
+        }
+    }
+    width
+}
+
+/// Separators used when a container is written on a single line.
+#[derive(Clone, Copy)]
+struct Spacing {
+    comma: &'static str,
+    colon: &'static str,
+}
+
+const COMPACT: Spacing = Spacing {
+    comma: ",",
+    colon: ":",
+};
+
+const INLINE: Spacing = Spacing {
+    comma: ", ",
+    colon: ": ",
+};
+
+fn write_flat<W: Write>(
+    w: &mut W,
+    v: &JsonValue,
+    opts: &SerializeOptions,
+    spacing: Spacing,
+) -> fmt::Result {
+    match v {
+        JsonValue::Str(s) => write_colored(w, STRING_COLOR, opts, |w| write_str(w, s, opts)),
+        JsonValue::Boolean(b) => write_colored(w, BOOLEAN_COLOR, opts, |w| write!(w, "{}", b)),
+        JsonValue::Null => write_colored(w, NULL_COLOR, opts, |w| w.write_str("null")),
+        JsonValue::Num(n) => write_colored(w, NUMBER_COLOR, opts, |w| write_num(w, *n)),
+        JsonValue::Array(arr) => {
+            w.write_char('[')?;
+            for (i, item) in arr.iter().enumerate() {
+                if i > 0 {
+                    w.write_str(spacing.comma)?;
+                }
+                write_flat(w, item, opts, spacing)?;
+            }
+            w.write_char(']')
+        }
+        JsonValue::Object(obj) => {
+            w.write_char('{')?;
+            for (i, (k, item)) in obj.iter().enumerate() {
+                if i > 0 {
+                    w.write_str(spacing.comma)?;
+                }
+                write_key(w, k, opts)?;
+                w.write_str(spacing.colon)?;
+                write_flat(w, item, opts, spacing)?;
+            }
+            w.write_char('}')
+        }
+    }
+}
+
+fn write_newline<W: Write>(w: &mut W, depth: usize, opts: &SerializeOptions) -> fmt::Result {
+    w.write_char('\n')?;
+    for _ in 0..depth {
+        w.write_str(&opts.indent_str)?;
+    }
+    Ok(())
+}
+
+/// Write `v` starting at `column`, with `reserved` characters (a trailing
+/// comma) following it on the same line.
+fn write_wrapped<W: Write>(
+    w: &mut W,
+    v: &JsonValue,
+    opts: &SerializeOptions,
+    width: usize,
+    depth: usize,
+    column: usize,
+    reserved: usize,
+) -> fmt::Result {
+    let mut inline = String::new();
+    write_flat(&mut inline, v, opts, INLINE)?;
+    let keep_inline = match v {
+        JsonValue::Array(arr) => {
+            arr.is_empty() || (opts.inline_scalar_arrays && arr.iter().all(JsonValue::is_scalar))
+        }
+        JsonValue::Object(obj) => obj.is_empty(),
+        _ => true,
+    };
+    if keep_inline || column + display_width(&inline) + reserved <= width {
+        return w.write_str(&inline);
+    }
+
+    let inner = (depth + 1) * opts.indent_str.chars().count();
+    match v {
+        JsonValue::Array(arr) => {
+            w.write_char('[')?;
+            for (i, item) in arr.iter().enumerate() {
+                let comma = if i + 1 < arr.len() || opts.emit_trailing_commas {
+                    1
+                } else {
+                    0
+                };
+                write_newline(w, depth + 1, opts)?;
+                write_wrapped(w, item, opts, width, depth + 1, inner, comma)?;
+                if comma > 0 {
+                    w.write_char(',')?;
+                }
+            }
+            write_newline(w, depth, opts)?;
+            w.write_char(']')
+        }
+        JsonValue::Object(obj) => {
+            w.write_char('{')?;
+            for (i, (k, item)) in obj.iter().enumerate() {
+                let comma = if i + 1 < obj.len() || opts.emit_trailing_commas {
+                    1
+                } else {
+                    0
+                };
+                let mut key = String::new();
+                write_key(&mut key, k, opts)?;
+                key.push_str(INLINE.colon);
+                write_newline(w, depth + 1, opts)?;
+                w.write_str(&key)?;
+                let column = inner + display_width(&key);
+                write_wrapped(w, item, opts, width, depth + 1, column, comma)?;
+                if comma > 0 {
+                    w.write_char(',')?;
+                }
+            }
+            write_newline(w, depth, opts)?;
+            w.write_char('}')
+        }
+        _ => unreachable!("scalars always fit"),
+    }
+}
+
+fn write_value<W: Write>(w: &mut W, v: &JsonValue, opts: &SerializeOptions) -> fmt::Result {
+    match opts.max_line_width {
+        Some(width) => write_wrapped(w, v, opts, width, 0, 0, 0),
+        None => write_flat(w, v, opts, COMPACT),
+    }
+}
+
+/// `fmt::Write` sink that only counts the bytes written to it.
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+/// `fmt::Write` sink appending UTF-8 straight to a byte buffer.
+struct ByteSink<'a>(&'a mut Vec<u8>);
+
+impl Write for ByteSink<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// Whether colors are wanted, given the value of `NO_COLOR`: any
+/// non-empty value turns them off (https://no-color.org).
+fn color_enabled(no_color: Option<OsString>) -> bool {
+    match no_color {
+        Some(v) => v.is_empty(),
+        None => true,
+    }
+}
+
+/// Layout of the `*_pretty*` helpers: 80 columns, two-space indent.
+fn pretty_options() -> SerializeOptions {
+    SerializeOptions {
+        max_line_width: Some(80),
+        ..SerializeOptions::default()
+    }
+}
+
+/// Compact JSON serialization.
+impl fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_value(f, self, &SerializeOptions::default())
+    }
+}
+
+impl JsonValue {
+    /// Consume the value and serialize it as compact JSON, same as `Display`.
+    pub fn into_json_string(self) -> String {
+        let mut out = String::new();
+        write_value(&mut out, &self, &SerializeOptions::default())
+            .expect("writing to a String cannot fail");
+        out
+    }
+
+    /// Multi-line output for terminals, 80 columns wide, with keys,
+    /// strings, numbers, booleans and null in distinct ANSI colors. Colors
+    /// are left out when the `NO_COLOR` environment variable is set to a
+    /// non-empty value.
+    pub fn to_pretty_colored(&self) -> String {
+        self.to_string_with(&SerializeOptions {
+            color: color_enabled(env::var_os("NO_COLOR")),
+            ..pretty_options()
+        })
+    }
+
+    /// Compact JSON as UTF-8 bytes, same text as `Display`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with(&SerializeOptions::default())
+    }
+
+    /// Multi-line JSON as UTF-8 bytes, laid out like `to_pretty_colored`
+    /// but without colors.
+    pub fn to_bytes_pretty(&self) -> Vec<u8> {
+        self.to_bytes_with(&pretty_options())
+    }
+
+    /// Multi-line JSON with every non-empty container expanded, one member
+    /// per line, indented by `indent_str` per nesting level. `indent_str`
+    /// should be JSON whitespace, e.g. `"\t"`; anything else yields
+    /// invalid JSON.
+    pub fn to_string_pretty_with(&self, indent_str: &str) -> String {
+        self.to_string_with(&SerializeOptions {
+            max_line_width: Some(0),
+            indent_str: String::from(indent_str),
+            ..SerializeOptions::default()
+        })
+    }
+
+    fn to_bytes_with(&self, opts: &SerializeOptions) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_value(&mut ByteSink(&mut out), self, opts).expect("writing to a Vec cannot fail");
+        out
+    }
+
+    /// Serialize with explicit options.
+    pub fn to_string_with(&self, opts: &SerializeOptions) -> String {
+        let mut out = String::new();
+        write_value(&mut out, self, opts).expect("writing to a String cannot fail");
+        out
+    }
+
+    /// Exact byte length of the compact serialization, computed without
+    /// building the output string.
+    pub fn serialized_len_estimate(&self) -> usize {
+        let mut counter = ByteCounter(0);
+        write_value(&mut counter, self, &SerializeOptions::default())
+            .expect("counting bytes cannot fail");
+        counter.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::root;
+
+    #[test]
+    fn test_display() {
+        let v = JsonValue::Array(vec![
+            JsonValue::Str(String::from("a\"b\\c\n\u{1}")),
+            JsonValue::Num(1.5),
+            JsonValue::Num(42.0),
+            JsonValue::Num(f64::NAN),
+            JsonValue::Boolean(true),
+            JsonValue::Null,
+            JsonValue::Array(vec![]),
+        ]);
+        assert_eq!(
+            format!("{}", v),
+            r#"["a\"b\\c\n\u0001",1.5,42,null,true,null,[]]"#
+        );
+    }
+
+    #[test]
+    fn test_into_json_string() {
+        let (_, v) = root(r#"{"key": "val", "arr": [1, true, null, {"x": []}]}"#).unwrap();
+        let expected = format!("{}", v);
+        assert_eq!(v.clone().into_json_string(), expected);
+        assert_eq!(root(&expected), Ok(("", v)));
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let (_, v) = root(r#"{"a": 1, "b": true, "a": 2}"#).unwrap();
+        match &v {
+            JsonValue::Object(obj) => {
+                assert_eq!(obj.len(), 2);
+                assert_eq!(obj.get("a"), Some(&JsonValue::Num(2.0)));
+            }
+            other => panic!("expected object, got {:?}", other),
+        }
+        let out = v.to_string();
+        assert_eq!(out.matches("\"a\"").count(), 1);
+        assert_eq!(root(&out), Ok(("", v)));
+    }
+
+    #[test]
+    fn test_to_bytes() {
+        let (_, v) = root(
+            r#"{"k": ["é", 1.25, {"deep": [true, null, "a very long string value"]}],
+            "other": "some more text to push this past eighty columns"}"#,
+        )
+        .unwrap();
+        assert_eq!(v.to_bytes(), v.to_string().into_bytes());
+        let pretty = v.to_string_with(&SerializeOptions {
+            max_line_width: Some(80),
+            ..SerializeOptions::default()
+        });
+        assert!(pretty.contains('\n'));
+        assert_eq!(v.to_bytes_pretty(), pretty.into_bytes());
+    }
+
+    #[test]
+    fn test_serialized_len_estimate() {
+        let (_, v) = root(r#"{"k\n": ["é", 1.25, -3, false, null, {}], "u": "\u0001"}"#).unwrap();
+        assert_eq!(v.serialized_len_estimate(), v.to_string().len());
+        let s = JsonValue::Str(String::from("\u{1}\"é"));
+        assert_eq!(s.serialized_len_estimate(), s.to_string().len());
+    }
+
+    #[test]
+    fn test_escape_policy() {
+        let v = JsonValue::Str(String::from("a<é>&'😀\u{2028}"));
+        let with = |escape| {
+            v.to_string_with(&SerializeOptions {
+                escape,
+                ..SerializeOptions::default()
+            })
+        };
+        assert_eq!(with(EscapePolicy::Minimal), "\"a<é>&'😀\u{2028}\"");
+        assert_eq!(v.to_string(), with(EscapePolicy::Minimal));
+        assert_eq!(
+            with(EscapePolicy::AsciiOnly),
+            r#""a<\u00e9>&'\ud83d\ude00\u2028""#
+        );
+        assert_eq!(
+            with(EscapePolicy::HtmlSafe),
+            r#""a\u003cé\u003e\u0026\u0027😀\u2028""#
+        );
+    }
+
+    #[test]
+    fn test_max_line_width() {
+        let opts = SerializeOptions {
+            max_line_width: Some(24),
+            ..SerializeOptions::default()
+        };
+        let (_, short) = root(r#"{"tags": ["a", "b"]}"#).unwrap();
+        assert_eq!(short.to_string_with(&opts), r#"{"tags": ["a", "b"]}"#);
+
+        let (_, long) = root(r#"[[1, 2, 3], {"name": "abcdefghijklmnop"}, [], "x"]"#).unwrap();
+        assert_eq!(
+            long.to_string_with(&opts),
+            "[\n  [1, 2, 3],\n  {\n    \"name\": \"abcdefghijklmnop\"\n  },\n  [],\n  \"x\"\n]"
+        );
+        assert_eq!(root(&long.to_string_with(&opts)), Ok(("", long)));
+    }
+
+    #[test]
+    fn test_number_syntax() {
+        let samples = [
+            0.0,
+            -0.0,
+            1.0,
+            -1.5,
+            1234567.125,
+            1e-7,
+            9007199254740993.0,
+            1e300,
+            -2.5e-300,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+        ];
+        for n in samples.iter() {
+            let out = JsonValue::Num(*n).to_string();
+            assert!(!out.contains(','), "{} has a separator", out);
+            assert_eq!(
+                root(&format!("[{}]", out)),
+                Ok(("", JsonValue::Array(vec![JsonValue::Num(*n)]))),
+                "{} does not round-trip",
+                out
+            );
+        }
+    }
+
+    #[test]
+    fn test_indent_str() {
+        let (_, v) = root(r#"{"a": [1, {"b": null}]}"#).unwrap();
+        let tabs = v.to_string_pretty_with("\t");
+        assert_eq!(
+            tabs,
+            "{\n\t\"a\": [\n\t\t1,\n\t\t{\n\t\t\t\"b\": null\n\t\t}\n\t]\n}"
+        );
+        let spaces = v.to_string_pretty_with("    ");
+        assert_eq!(
+            spaces,
+            "{\n    \"a\": [\n        1,\n        {\n            \"b\": null\n        }\n    ]\n}"
+        );
+        assert_eq!(root(&tabs), Ok(("", v.clone())));
+        assert_eq!(root(&spaces), Ok(("", v.clone())));
+
+        // the same unit under a width limit
+        let wrapped = v.to_string_with(&SerializeOptions {
+            max_line_width: Some(16),
+            indent_str: String::from("\t"),
+            ..SerializeOptions::default()
+        });
+        assert_eq!(wrapped, "{\n\t\"a\": [\n\t\t1,\n\t\t{\"b\": null}\n\t]\n}");
+    }
+
+    #[test]
+    fn test_inline_scalar_arrays() {
+        let (_, v) =
+            root(r#"{"tags": ["a", "b", "c"], "items": [{"id": 1}], "grid": [[1, 2]]}"#).unwrap();
+        let opts = SerializeOptions {
+            max_line_width: Some(0),
+            inline_scalar_arrays: true,
+            ..SerializeOptions::default()
+        };
+        let out = v.to_string_with(&opts);
+        assert!(out.contains("\n  \"tags\": [\"a\", \"b\", \"c\"]"));
+        assert!(out.contains("\"items\": [\n    {\n      \"id\": 1\n    }\n  ]"));
+        assert!(out.contains("\"grid\": [\n    [1, 2]\n  ]"));
+        assert_eq!(root(&out), Ok(("", v)));
+    }
+
+    #[test]
+    fn test_color() {
+        let (_, v) = root(r#"{"k": ["s", 1.5, true, null]}"#).unwrap();
+        let colored = v.to_string_with(&SerializeOptions {
+            color: true,
+            ..SerializeOptions::default()
+        });
+        assert_eq!(
+            colored,
+            "{\x1b[1;34m\"k\"\x1b[0m:[\x1b[32m\"s\"\x1b[0m,\x1b[36m1.5\x1b[0m,\
+             \x1b[33mtrue\x1b[0m,\x1b[1;30mnull\x1b[0m]}"
+        );
+
+        // color codes take no columns, so wrapping is unchanged
+        let wrapped = |color| {
+            v.to_string_with(&SerializeOptions {
+                max_line_width: Some(29),
+                color,
+                ..SerializeOptions::default()
+            })
+        };
+        assert_eq!(wrapped(false), "{\"k\": [\"s\", 1.5, true, null]}");
+        assert!(!wrapped(true).contains('\n'));
+        assert_eq!(display_width(&wrapped(true)), 29);
+        assert!(!wrapped(false).contains('\x1b'));
+    }
+
+    #[test]
+    fn test_color_enabled() {
+        assert!(color_enabled(None));
+        assert!(color_enabled(Some(OsString::new())));
+        assert!(!color_enabled(Some(OsString::from("1"))));
+        assert!(!color_enabled(Some(OsString::from("false"))));
+    }
+
+    #[test]
+    fn test_to_pretty_colored() {
+        let (_, v) = root(r#"{"k": ["s", 1.5, true, null]}"#).unwrap();
+        let color = color_enabled(env::var_os("NO_COLOR"));
+        assert_eq!(
+            v.to_pretty_colored(),
+            v.to_string_with(&SerializeOptions {
+                color,
+                ..pretty_options()
+            })
+        );
+        assert_eq!(v.to_pretty_colored().contains('\x1b'), color);
+    }
+
+    #[test]
+    fn test_emit_trailing_commas() {
+        let (_, v) = root(r#"[[1, 2], {"name": "abcdefghijklmnop"}, [], "x"]"#).unwrap();
+        let with = |emit_trailing_commas| {
+            v.to_string_with(&SerializeOptions {
+                max_line_width: Some(24),
+                emit_trailing_commas,
+                ..SerializeOptions::default()
+            })
+        };
+        assert_eq!(
+            with(true),
+            "[\n  [1, 2],\n  {\n    \"name\": \"abcdefghijklmnop\",\n  },\n  [],\n  \"x\",\n]"
+        );
+        assert_eq!(
+            with(true).replace(",\n]", "\n]").replace(",\n  }", "\n  }"),
+            with(false)
+        );
+        // single-line output is unaffected
+        assert_eq!(
+            v.to_string_with(&SerializeOptions {
+                emit_trailing_commas: true,
+                ..SerializeOptions::default()
+            }),
+            v.to_string()
+        );
+    }
+
+    #[test]
+    fn test_control_escapes_round_trip() {
+        let v = JsonValue::Str(String::from("\u{8}\u{c}"));
+        assert_eq!(v.to_string(), r#""\b\f""#);
+
+        let input = r#"{"b\b": ["\b", "x\fy", "\\b\\f", "\u0008"]}"#;
+        let (_, parsed) = root(input).unwrap();
+        assert_eq!(
+            parsed.pointer("/b\u{8}/1"),
+            Some(&JsonValue::Str(String::from("x\u{c}y")))
+        );
+        let out = parsed.to_string();
+        assert_eq!(out, r#"{"b\b":["\b","x\fy","\\b\\f","\b"]}"#);
+        assert_eq!(root(&out), Ok(("", parsed)));
+    }
+
+    #[test]
+    fn test_empty_containers_stay_inline() {
+        let tight = |v: &JsonValue| {
+            v.to_string_with(&SerializeOptions {
+                max_line_width: Some(0),
+                emit_trailing_commas: true,
+                ..SerializeOptions::default()
+            })
+        };
+        assert_eq!(tight(&JsonValue::Array(vec![])), "[]");
+        assert_eq!(tight(&JsonValue::object_from_pairs(vec![])), "{}");
+
+        let (_, nested) = root(r#"[[], {}, [[]], {"a": {}}]"#).unwrap();
+        assert_eq!(
+            tight(&nested),
+            "[\n  [],\n  {},\n  [\n    [],\n  ],\n  {\n    \"a\": {},\n  },\n]"
+        );
+        assert!(!nested.to_bytes_pretty().contains(&b'\n'));
+    }
+}
@@ -0,0 +1,629 @@
+use crate::parser::{describe_error, document, skip_value, value, JsonValue, ParseOptions};
+use nom::error::{ErrorKind, VerboseError};
+use std::{error, fmt, io, str};
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+#[derive(Debug)]
+pub enum DocumentError {
+    Io(io::Error),
+    /// The stream is not valid UTF-8.
+    Utf8,
+    /// A document is malformed; holds the rendered parser error.
+    Syntax(String),
+}
+
+impl fmt::Display for DocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DocumentError::Io(e) => write!(f, "read error: {}", e),
+            DocumentError::Utf8 => f.write_str("stream is not valid UTF-8"),
+            DocumentError::Syntax(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl error::Error for DocumentError {}
+
+/// Iterator over concatenated JSON documents, see `documents`.
+pub struct Documents<R> {
+    reader: R,
+    buf: Vec<u8>,
+    eof: bool,
+    done: bool,
+    bom_checked: bool,
+    // boundary scan over buf[..scanned] of the document starting at `start`
+    scanned: usize,
+    start: usize,
+    state: Scan,
+    closers: Vec<u8>,
+    in_string: bool,
+    escaped: bool,
+}
+
+/// Where the boundary scan of `Documents` is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scan {
+    /// Skipping whitespace before the next document.
+    Between,
+    /// Inside an object or array; `closers` holds the brackets still open.
+    Container,
+    /// Inside a top-level string.
+    Str,
+    /// Inside a top-level number or literal.
+    Scalar,
+}
+
+const BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// Lazily parse successive top-level JSON values from `reader`.
+///
+/// Documents may be separated by whitespace or simply follow each other
+/// (`{...}[...]"str"`); any JSON value is accepted at the top level. One
+/// byte order mark is skipped at the very start of the stream. Input is
+/// read in chunks and scanned once to find where each document ends;
+/// only then is the document parsed, so a live stream gets each document
+/// without waiting for the next one. Only a number at the very end of the
+/// buffer waits for more input, as `12` may be split as `1`/`2`. A
+/// malformed document is reported once it ends, e.g. at its closing or
+/// mismatched bracket. Iteration ends after the first error.
+pub fn documents<R: io::Read>(reader: R) -> Documents<R> {
+    Documents {
+        reader,
+        buf: Vec::new(),
+        eof: false,
+        done: false,
+        bom_checked: false,
+        scanned: 0,
+        start: 0,
+        state: Scan::Between,
+        closers: Vec::new(),
+        in_string: false,
+        escaped: false,
+    }
+}
+
+impl<R: io::Read> Documents<R> {
+    fn fill(&mut self) -> Result<(), DocumentError> {
+        let start = self.buf.len();
+        self.buf.resize(start + CHUNK_SIZE, 0);
+        let read = loop {
+            match self.reader.read(&mut self.buf[start..]) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                other => break other,
+            }
+        };
+        match read {
+            Ok(n) => {
+                self.buf.truncate(start + n);
+                self.eof = n == 0;
+                Ok(())
+            }
+            Err(e) => {
+                self.buf.truncate(start);
+                Err(DocumentError::Io(e))
+            }
+        }
+    }
+
+    /// Continue the boundary scan; the end offset of the current document
+    /// once it is complete. A mismatched closing bracket also ends it, so
+    /// parsing can report the mismatch. Bytes that are part of a
+    /// multi-byte character never look like ASCII delimiters.
+    fn scan(&mut self) -> Option<usize> {
+        while self.scanned < self.buf.len() {
+            let b = self.buf[self.scanned];
+            self.scanned += 1;
+            match self.state {
+                Scan::Between => {
+                    if b" \t\n\r".contains(&b) {
+                        continue;
+                    }
+                    self.start = self.scanned - 1;
+                    self.state = match b {
+                        b'{' | b'[' => {
+                            self.closers.push(if b == b'{' { b'}' } else { b']' });
+                            Scan::Container
+                        }
+                        b'"' => Scan::Str,
+                        _ => Scan::Scalar,
+                    };
+                }
+                Scan::Str => match b {
+                    _ if self.escaped => self.escaped = false,
+                    b'\\' => self.escaped = true,
+                    b'"' => return Some(self.scanned),
+                    _ => {}
+                },
+                Scan::Container if self.in_string => match b {
+                    _ if self.escaped => self.escaped = false,
+                    b'\\' => self.escaped = true,
+                    b'"' => self.in_string = false,
+                    _ => {}
+                },
+                Scan::Container => match b {
+                    b'"' => self.in_string = true,
+                    b'{' => self.closers.push(b'}'),
+                    b'[' => self.closers.push(b']'),
+                    // always pops; the outermost or a mismatched closer ends it
+                    b'}' | b']' if self.closers.pop() != Some(b) || self.closers.is_empty() => {
+                        return Some(self.scanned);
+                    }
+                    _ => {}
+                },
+                Scan::Scalar => {
+                    if b" \t\n\r{}[],:\"".contains(&b) {
+                        self.scanned -= 1;
+                        return Some(self.scanned);
+                    }
+                }
+            }
+        }
+        // unlike a number, a literal cannot grow any further
+        let tail = &self.buf[self.start..];
+        if self.state == Scan::Scalar && (tail == b"true" || tail == b"false" || tail == b"null") {
+            return Some(self.buf.len());
+        }
+        None
+    }
+
+    /// Parse `buf[start..end]` and drop everything up to `end`.
+    fn take(&mut self, end: usize) -> Result<JsonValue, DocumentError> {
+        let parsed = match str::from_utf8(&self.buf[self.start..end]) {
+            Ok(text) => parse_value(text),
+            Err(_) => Err(DocumentError::Utf8),
+        };
+        self.buf.drain(..end);
+        self.scanned = 0;
+        self.start = 0;
+        self.state = Scan::Between;
+        self.closers.clear();
+        self.in_string = false;
+        self.escaped = false;
+        parsed
+    }
+
+    fn next_document(&mut self) -> Option<Result<JsonValue, DocumentError>> {
+        loop {
+            if !self.bom_checked && (self.eof || !BOM.starts_with(&self.buf)) {
+                if self.buf.starts_with(BOM) {
+                    self.buf.drain(..BOM.len());
+                }
+                self.bom_checked = true;
+            }
+            if self.bom_checked {
+                if let Some(end) = self.scan() {
+                    return Some(self.take(end));
+                }
+                if self.eof {
+                    return match self.state {
+                        Scan::Between => None,
+                        _ => Some(self.take(self.buf.len())),
+                    };
+                }
+            }
+            if let Err(e) = self.fill() {
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+/// Parse one complete document, with cheap errors first and rich ones
+/// only to describe a failure.
+fn parse_value(text: &str) -> Result<JsonValue, DocumentError> {
+    let opts = ParseOptions::default();
+    if let Ok(("", v)) = value::<(&str, ErrorKind)>(text, &opts) {
+        return Ok(v);
+    }
+    let error = match value::<VerboseError<&str>>(text, &opts) {
+        Ok(("", v)) => return Ok(v),
+        Ok((rest, _)) => format!("unexpected `{}` after value", rest),
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => describe_error(text, e),
+        Err(nom::Err::Incomplete(_)) => String::from("incomplete value"),
+    };
+    Err(DocumentError::Syntax(error))
+}
+
+impl<R: io::Read> Iterator for Documents<R> {
+    type Item = Result<JsonValue, DocumentError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_document();
+        if let Some(Err(_)) | None = next {
+            self.done = true;
+        }
+        next
+    }
+}
+
+/// Push-style parser for a document that arrives in pieces, e.g. from a
+/// socket: `feed` bytes as they come and call `try_finish` to see whether
+/// a whole top-level object or array is there yet.
+#[derive(Debug, Default)]
+pub struct Parser {
+    buf: Vec<u8>,
+    bom_checked: bool,
+    // bracket scan over buf[..scanned], outside of strings
+    scanned: usize,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Parser::default()
+    }
+
+    /// Append the next chunk; it may end in the middle of a token or of a
+    /// multi-byte character.
+    pub fn feed(&mut self, chunk: &[u8]) {
+        self.buf.extend_from_slice(chunk);
+    }
+
+    /// Continue the bracket scan; the end offset of the first document
+    /// once its brackets balance. Bytes that are part of a multi-byte
+    /// character never look like ASCII brackets or quotes.
+    fn scan(&mut self) -> Option<usize> {
+        while self.scanned < self.buf.len() {
+            let b = self.buf[self.scanned];
+            self.scanned += 1;
+            if self.in_string {
+                match b {
+                    _ if self.escaped => self.escaped = false,
+                    b'\\' => self.escaped = true,
+                    b'"' => self.in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match b {
+                b'"' => self.in_string = true,
+                b'[' | b'{' => self.depth += 1,
+                b']' | b'}' => {
+                    self.depth = self.depth.saturating_sub(1);
+                    if self.depth == 0 {
+                        return Some(self.scanned);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// The document, once all of it has been fed, or `Ok(None)` while it
+    /// is still incomplete. A returned document is removed from the buffer
+    /// and anything fed after it is kept for the next call.
+    ///
+    /// Completeness is judged by matching brackets, so most syntax errors
+    /// are only reported once the brackets balance. The malformed document
+    /// is then dropped, and the next call starts on whatever follows it. A
+    /// top-level scalar is rejected straight away, and everything before
+    /// the next `{` or `[` is dropped with it.
+    pub fn try_finish(&mut self) -> Result<Option<JsonValue>, DocumentError> {
+        // only the very start of the input may hold a byte order mark
+        if !self.bom_checked {
+            if BOM.starts_with(&self.buf) {
+                return Ok(None);
+            }
+            if self.buf.starts_with(BOM) {
+                self.buf.drain(..BOM.len());
+            }
+            self.bom_checked = true;
+        }
+        match self.buf.iter().position(|b| !b" \t\n\r".contains(b)) {
+            None => return Ok(None),
+            Some(offset) if self.buf[offset] != b'{' && self.buf[offset] != b'[' => {
+                let skip = self.buf[offset..]
+                    .iter()
+                    .position(|b| *b == b'{' || *b == b'[')
+                    .map_or(self.buf.len(), |n| offset + n);
+                self.buf.drain(..skip);
+                return Err(DocumentError::Syntax(format!(
+                    "expected an object or array at offset {}",
+                    offset
+                )));
+            }
+            Some(_) => {}
+        }
+        let end = match self.scan() {
+            Some(end) => end,
+            None => return Ok(None),
+        };
+        let parsed = match str::from_utf8(&self.buf[..end]) {
+            Err(_) => Err(DocumentError::Utf8),
+            Ok(text) => match document::<VerboseError<&str>>(text, &ParseOptions::default()) {
+                Ok((_, v)) => Ok(Some(v)),
+                Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+                    Err(DocumentError::Syntax(describe_error(text, e)))
+                }
+                Err(nom::Err::Incomplete(_)) => Ok(None),
+            },
+        };
+        self.buf.drain(..end);
+        self.scanned = 0;
+        parsed
+    }
+}
+
+/// Outcome of `validate_lines`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ValidationReport {
+    /// Non-blank lines seen.
+    pub total: usize,
+    pub valid: usize,
+    /// 1-based line number and rendered error of every invalid line.
+    pub errors: Vec<(usize, String)>,
+}
+
+/// Check that every non-blank line of `input` holds exactly one JSON value
+/// (NDJSON), collecting all failures instead of stopping at the first.
+///
+/// Lines are only checked, never built into values. Only JSON whitespace
+/// (space, tab, `\r`) may surround a value.
+pub fn validate_lines(input: &str) -> ValidationReport {
+    let opts = ParseOptions::default();
+    let mut report = ValidationReport::default();
+    for (n, line) in input.lines().enumerate() {
+        let line = line.trim_matches(|c| c == ' ' || c == '\t' || c == '\r');
+        if line.is_empty() {
+            continue;
+        }
+        report.total += 1;
+        if let Ok(("", ())) = skip_value::<(&str, ErrorKind)>(line, &opts) {
+            report.valid += 1;
+            continue;
+        }
+        // check again with rich errors to describe the failure
+        let error = match skip_value::<VerboseError<&str>>(line, &opts) {
+            Ok(("", ())) => {
+                report.valid += 1;
+                continue;
+            }
+            Ok((rest, ())) => format!("unexpected `{}` after value", rest),
+            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => describe_error(line, e),
+            Err(nom::Err::Incomplete(_)) => String::from("incomplete value"),
+        };
+        report.errors.push((n + 1, error));
+    }
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::root;
+
+    /// Hands out one byte per `read` call to exercise buffering.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((b, rest)) if !buf.is_empty() => {
+                    buf[0] = *b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_documents() {
+        let input = r#"{"a": [1, "é"]}[12, true]"str" 345"#;
+        let (_, obj) = root(r#"{"a": [1, "é"]}"#).unwrap();
+        let (_, arr) = root("[12, true]").unwrap();
+        let expected = vec![
+            obj,
+            arr,
+            JsonValue::Str(String::from("str")),
+            JsonValue::Num(345.0),
+        ];
+
+        let values: Vec<_> = documents(input.as_bytes()).map(Result::unwrap).collect();
+        assert_eq!(values, expected);
+        let values: Vec<_> = documents(Trickle(input.as_bytes()))
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(values, expected);
+
+        assert_eq!(documents(&b"  \n "[..]).count(), 0);
+    }
+
+    #[test]
+    fn test_documents_large() {
+        let item = r#"{"id": 1, "tags": ["a", "]", "\"}"], "ok": true}"#;
+        let big = format!("[{}]", vec![item; 1000].join(", "));
+        assert!(big.len() > 4 * CHUNK_SIZE);
+        let (_, expected) = root(&big).unwrap();
+        let input = format!("{}\n{} 7", big, big);
+        let values: Vec<_> = documents(input.as_bytes()).map(Result::unwrap).collect();
+        assert_eq!(
+            values,
+            vec![expected.clone(), expected, JsonValue::Num(7.0)]
+        );
+    }
+
+    #[test]
+    fn test_documents_byte_order_mark() {
+        let input = b"\xef\xbb\xbf[1] [2]";
+        let expected = vec![
+            JsonValue::Array(vec![JsonValue::Num(1.0)]),
+            JsonValue::Array(vec![JsonValue::Num(2.0)]),
+        ];
+        let values: Vec<_> = documents(&input[..]).map(Result::unwrap).collect();
+        assert_eq!(values, expected);
+        let values: Vec<_> = documents(Trickle(input)).map(Result::unwrap).collect();
+        assert_eq!(values, expected);
+
+        let mut docs = documents(&b"[1] \xef\xbb\xbf[2]"[..]);
+        assert_eq!(docs.next().unwrap().unwrap(), expected[0]);
+        assert!(matches!(docs.next(), Some(Err(DocumentError::Syntax(_)))));
+    }
+
+    #[test]
+    fn test_documents_error() {
+        let mut docs = documents(&br#"[1] {"a" 1} [2]"#[..]);
+        assert_eq!(
+            docs.next().unwrap().unwrap(),
+            JsonValue::Array(vec![JsonValue::Num(1.0)])
+        );
+        match docs.next() {
+            Some(Err(DocumentError::Syntax(_))) => {}
+            other => panic!("expected a syntax error, got {:?}", other),
+        }
+        assert!(docs.next().is_none());
+
+        match documents(&b"[\"\xff\"]"[..]).next() {
+            Some(Err(DocumentError::Utf8)) => {}
+            other => panic!("expected a UTF-8 error, got {:?}", other),
+        }
+    }
+
+    /// Hands out its data in one `read`, then fails like a socket with
+    /// nothing more to give yet.
+    struct Live<'a>(&'a [u8]);
+
+    impl io::Read for Live<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Err(io::Error::from(io::ErrorKind::WouldBlock));
+            }
+            let n = self.0.len().min(buf.len());
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_documents_live() {
+        let (_, obj) = root(r#"{"a": 1}"#).unwrap();
+        let mut docs = documents(Live(b"{\"a\": 1}\n"));
+        assert_eq!(docs.next().unwrap().unwrap(), obj);
+        assert!(matches!(docs.next(), Some(Err(DocumentError::Io(_)))));
+
+        let mut docs = documents(Live(b"[1, \"a\"] \"b\" true 12 3"));
+        assert_eq!(
+            docs.next().unwrap().unwrap(),
+            JsonValue::Array(vec![JsonValue::Num(1.0), JsonValue::Str(String::from("a"))])
+        );
+        assert_eq!(
+            docs.next().unwrap().unwrap(),
+            JsonValue::Str(String::from("b"))
+        );
+        assert_eq!(docs.next().unwrap().unwrap(), JsonValue::Boolean(true));
+        assert_eq!(docs.next().unwrap().unwrap(), JsonValue::Num(12.0));
+        // `3` could still grow into `34`
+        assert!(matches!(docs.next(), Some(Err(DocumentError::Io(_)))));
+
+        for input in &[&b"[1, tru x]"[..], b"{\"a\": [1}", b"nulx ", b"1-2 "] {
+            match documents(Live(input)).next() {
+                Some(Err(DocumentError::Syntax(_))) => {}
+                other => panic!("expected a syntax error, got {:?}", other),
+            }
+        }
+        // a malformed container is only reported once it is closed
+        for input in &[
+            &b"[1, tr"[..],
+            b"[\"a b",
+            b"{\"a\": 1e",
+            b"nul",
+            b"[1, tru x",
+        ] {
+            assert!(matches!(
+                documents(Live(input)).next(),
+                Some(Err(DocumentError::Io(_)))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_validate_lines() {
+        let input = "{\"a\": 1}\n[1, 2\n\n\"x\"\n{\"a\" 1}\n3 4\n  true  \n";
+        let report = validate_lines(input);
+        assert_eq!(report.total, 6);
+        assert_eq!(report.valid, 3);
+        let lines: Vec<_> = report.errors.iter().map(|(n, _)| *n).collect();
+        assert_eq!(lines, vec![2, 5, 6]);
+        assert_eq!(report.errors[2].1, "unexpected `4` after value");
+
+        assert_eq!(validate_lines("\n \n"), ValidationReport::default());
+        let report = validate_lines("\t[1]\r\n\u{a0}[1]\u{a0}\n");
+        assert_eq!(report.valid, 1);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].0, 2);
+    }
+
+    #[test]
+    fn test_parser() {
+        let input = r#"{"a": [1, true, null, "é\"]"], "b": -1.5e3} [2]"#;
+        let mut parser = Parser::new();
+        let (_, expected) = root(r#"{"a": [1, true, null, "é\"]"], "b": -1.5e3}"#).unwrap();
+        let split = input.find(" [2]").unwrap();
+        for &b in &input.as_bytes()[..split] {
+            assert_eq!(parser.try_finish().unwrap(), None);
+            parser.feed(&[b]);
+        }
+        assert_eq!(parser.try_finish().unwrap(), Some(expected));
+        assert_eq!(parser.try_finish().unwrap(), None);
+        parser.feed(&input.as_bytes()[split..]);
+        assert_eq!(
+            parser.try_finish().unwrap(),
+            Some(JsonValue::Array(vec![JsonValue::Num(2.0)]))
+        );
+
+        let mut parser = Parser::new();
+        parser.feed(b"[1, tru");
+        assert_eq!(parser.try_finish().unwrap(), None);
+        parser.feed(b"x] [2]");
+        assert!(parser.try_finish().is_err());
+        assert_eq!(
+            parser.try_finish().unwrap(),
+            Some(JsonValue::Array(vec![JsonValue::Num(2.0)]))
+        );
+
+        let mut parser = Parser::new();
+        for &b in b"\xef\xbb\xbf[1" {
+            parser.feed(&[b]);
+            assert_eq!(parser.try_finish().unwrap(), None);
+        }
+        parser.feed(b"]");
+        assert_eq!(
+            parser.try_finish().unwrap(),
+            Some(JsonValue::Array(vec![JsonValue::Num(1.0)]))
+        );
+        // a byte order mark is only skipped before the first document
+        parser.feed(b"\xef\xbb\xbf[2]");
+        assert!(parser.try_finish().is_err());
+        assert_eq!(
+            parser.try_finish().unwrap(),
+            Some(JsonValue::Array(vec![JsonValue::Num(2.0)]))
+        );
+
+        let mut parser = Parser::new();
+        parser.feed(b"12 [3]");
+        assert!(parser.try_finish().is_err());
+        assert_eq!(
+            parser.try_finish().unwrap(),
+            Some(JsonValue::Array(vec![JsonValue::Num(3.0)]))
+        );
+
+        let mut parser = Parser::new();
+        parser.feed(b"[1, }");
+        assert!(parser.try_finish().is_err());
+        let mut parser = Parser::new();
+        parser.feed(b" 12");
+        assert!(parser.try_finish().is_err());
+        let mut parser = Parser::new();
+        parser.feed(b"[\xff]");
+        assert!(matches!(parser.try_finish(), Err(DocumentError::Utf8)));
+    }
+}
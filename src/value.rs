@@ -0,0 +1,999 @@
+use crate::parser::JsonValue;
+use std::{collections::HashMap, error, fmt, mem};
+
+/// Why a `try_as_*` numeric conversion failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NumberError {
+    /// The value is not a number; holds what it is instead, e.g.
+    /// `"a string"`.
+    NotANumber(&'static str),
+    /// The number has a fractional part (or is not finite).
+    NotAnInteger(f64),
+    /// The integer does not fit the target type, named by `target`.
+    OutOfRange { value: f64, target: &'static str },
+}
+
+impl fmt::Display for NumberError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NumberError::NotANumber(kind) => write!(f, "value is {}, not a number", kind),
+            NumberError::NotAnInteger(n) => write!(f, "value {} is not an integer", n),
+            NumberError::OutOfRange { value, target } => {
+                write!(f, "value {} out of range for {}", value, target)
+            }
+        }
+    }
+}
+
+impl error::Error for NumberError {}
+
+/// How many nodes of each kind a tree holds, see `count_by_kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct KindCounts {
+    pub strings: usize,
+    pub numbers: usize,
+    pub booleans: usize,
+    pub nulls: usize,
+    pub arrays: usize,
+    pub objects: usize,
+}
+
+impl JsonValue {
+    /// Build an object from key/value pairs; later duplicates win.
+    pub fn object_from_pairs<I>(pairs: I) -> JsonValue
+    where
+        I: IntoIterator<Item = (String, JsonValue)>,
+    {
+        JsonValue::Object(pairs.into_iter().collect())
+    }
+
+    /// Build an array from values, keeping their order.
+    pub fn array_from_values<I>(values: I) -> JsonValue
+    where
+        I: IntoIterator<Item = JsonValue>,
+    {
+        JsonValue::Array(values.into_iter().collect())
+    }
+
+    /// The `null` value.
+    pub fn null() -> JsonValue {
+        JsonValue::Null
+    }
+
+    /// `Some(())` if this is `null`, for chaining with the other accessors.
+    pub fn as_null(&self) -> Option<()> {
+        match self {
+            JsonValue::Null => Some(()),
+            _ => None,
+        }
+    }
+
+    /// The number as an integer in `[min, max)`, both exact in an `f64`.
+    fn try_integer(&self, target: &'static str, min: f64, max: f64) -> Result<f64, NumberError> {
+        let n = match self {
+            JsonValue::Num(n) => *n,
+            JsonValue::Str(_) => return Err(NumberError::NotANumber("a string")),
+            JsonValue::Boolean(_) => return Err(NumberError::NotANumber("a boolean")),
+            JsonValue::Null => return Err(NumberError::NotANumber("null")),
+            JsonValue::Array(_) => return Err(NumberError::NotANumber("an array")),
+            JsonValue::Object(_) => return Err(NumberError::NotANumber("an object")),
+        };
+        if n.fract() != 0.0 || !n.is_finite() {
+            return Err(NumberError::NotAnInteger(n));
+        }
+        if n < min || n >= max {
+            return Err(NumberError::OutOfRange { value: n, target });
+        }
+        Ok(n)
+    }
+
+    /// The number as a `u8`, or why it is not one.
+    pub fn try_as_u8(&self) -> Result<u8, NumberError> {
+        self.try_integer("u8", 0.0, 256.0).map(|n| n as u8)
+    }
+
+    /// The number as a `u16`, or why it is not one.
+    pub fn try_as_u16(&self) -> Result<u16, NumberError> {
+        self.try_integer("u16", 0.0, 65536.0).map(|n| n as u16)
+    }
+
+    /// The number as a `u32`, or why it is not one.
+    pub fn try_as_u32(&self) -> Result<u32, NumberError> {
+        self.try_integer("u32", 0.0, 4294967296.0).map(|n| n as u32)
+    }
+
+    /// The number as a `u64`, or why it is not one.
+    pub fn try_as_u64(&self) -> Result<u64, NumberError> {
+        self.try_integer("u64", 0.0, 18446744073709551616.0)
+            .map(|n| n as u64)
+    }
+
+    /// The number as an `i32`, or why it is not one.
+    pub fn try_as_i32(&self) -> Result<i32, NumberError> {
+        self.try_integer("i32", -2147483648.0, 2147483648.0)
+            .map(|n| n as i32)
+    }
+
+    /// The number as an `i64`, or why it is not one.
+    pub fn try_as_i64(&self) -> Result<i64, NumberError> {
+        self.try_integer("i64", -9223372036854775808.0, 9223372036854775808.0)
+            .map(|n| n as i64)
+    }
+
+    /// `true` for strings, numbers, booleans and null.
+    pub fn is_scalar(&self) -> bool {
+        !self.is_container()
+    }
+
+    /// `true` for arrays and objects.
+    pub fn is_container(&self) -> bool {
+        matches!(self, JsonValue::Array(_) | JsonValue::Object(_))
+    }
+
+    /// First element of an array; `None` for empty arrays and non-arrays.
+    pub fn first(&self) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Array(arr) => arr.first(),
+            _ => None,
+        }
+    }
+
+    /// Last element of an array; `None` for empty arrays and non-arrays.
+    pub fn last(&self) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Array(arr) => arr.last(),
+            _ => None,
+        }
+    }
+
+    /// Mutable counterpart of `first`.
+    pub fn first_mut(&mut self) -> Option<&mut JsonValue> {
+        match self {
+            JsonValue::Array(arr) => arr.first_mut(),
+            _ => None,
+        }
+    }
+
+    /// Mutable counterpart of `last`.
+    pub fn last_mut(&mut self) -> Option<&mut JsonValue> {
+        match self {
+            JsonValue::Array(arr) => arr.last_mut(),
+            _ => None,
+        }
+    }
+
+    /// Move the member at `from` to `to`, replacing any existing `to`.
+    ///
+    /// Returns `false`, leaving the value untouched, when this is not an
+    /// object or `from` is absent. Objects are unordered, so there is no
+    /// position to preserve.
+    pub fn rename_key(&mut self, from: &str, to: &str) -> bool {
+        match self {
+            JsonValue::Object(obj) => match obj.remove(from) {
+                Some(v) => {
+                    obj.insert(String::from(to), v);
+                    true
+                }
+                None => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Member `key` of an object; `None` for non-objects.
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(obj) => obj.get(key),
+            _ => None,
+        }
+    }
+
+    /// Member `key` if it is a string; `None` if absent or another type.
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        match self.get(key)? {
+            JsonValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Member `key` if it is a number; `None` if absent or another type.
+    pub fn get_f64(&self, key: &str) -> Option<f64> {
+        match self.get(key)? {
+            JsonValue::Num(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Member `key` if it is a boolean; `None` if absent or another type.
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.get(key)? {
+            JsonValue::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Member `key` if it is an array; `None` if absent or another type.
+    pub fn get_array(&self, key: &str) -> Option<&[JsonValue]> {
+        match self.get(key)? {
+            JsonValue::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    /// Member `key` if it is an object; `None` if absent or another type.
+    pub fn get_object(&self, key: &str) -> Option<&HashMap<String, JsonValue>> {
+        match self.get(key)? {
+            JsonValue::Object(obj) => Some(obj),
+            _ => None,
+        }
+    }
+
+    /// Check that every key in `keys` is a member of this object.
+    ///
+    /// The error lists the missing keys in the order given. A non-object
+    /// is always an error, with every key reported missing, even when
+    /// `keys` is empty.
+    pub fn require_keys(&self, keys: &[&str]) -> Result<(), Vec<String>> {
+        let obj = match self {
+            JsonValue::Object(obj) => obj,
+            _ => return Err(keys.iter().map(|k| String::from(*k)).collect()),
+        };
+        let missing: Vec<String> = keys
+            .iter()
+            .filter(|k| !obj.contains_key(**k))
+            .map(|k| String::from(*k))
+            .collect();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(missing)
+        }
+    }
+
+    /// Elements of an array, or any other value as a single element.
+    ///
+    /// Smooths over APIs that return either one item or a list of items;
+    /// note that `null` also counts as a single element.
+    pub fn as_array_coerce(&self) -> Vec<&JsonValue> {
+        match self {
+            JsonValue::Array(arr) => arr.iter().collect(),
+            other => vec![other],
+        }
+    }
+
+    /// Owning counterpart of `as_array_coerce`: arrays are returned as
+    /// they are, anything else (including `null`) becomes a one-element
+    /// array.
+    pub fn ensure_array(self) -> JsonValue {
+        match self {
+            JsonValue::Array(_) => self,
+            other => JsonValue::Array(vec![other]),
+        }
+    }
+
+    /// Objects are returned as they are; anything else becomes the single
+    /// member `key` of a new object.
+    pub fn ensure_object(self, key: &str) -> JsonValue {
+        match self {
+            JsonValue::Object(_) => self,
+            other => JsonValue::object_from_pairs(vec![(String::from(key), other)]),
+        }
+    }
+
+    /// Remove every element or member of an array or object, keeping its
+    /// allocation. Scalars are left unchanged.
+    pub fn clear(&mut self) {
+        match self {
+            JsonValue::Array(arr) => arr.clear(),
+            JsonValue::Object(obj) => obj.clear(),
+            _ => {}
+        }
+    }
+
+    /// Shorten an array to at most `len` elements. Objects and scalars are
+    /// left unchanged.
+    pub fn truncate(&mut self, len: usize) {
+        if let JsonValue::Array(arr) = self {
+            arr.truncate(len);
+        }
+    }
+
+    /// Deep equality that treats arrays as multisets: elements must pair up
+    /// one-to-one in any order, so `[1, 2, 3]` equals `[3, 2, 1]` but not
+    /// `[1, 2, 2]`. Objects and scalars compare as usual, recursively.
+    ///
+    /// Matching array elements is a greedy search, O(n²) comparisons per
+    /// array.
+    pub fn deep_equal_unordered(&self, other: &JsonValue) -> bool {
+        match (self, other) {
+            (JsonValue::Array(a), JsonValue::Array(b)) => {
+                if a.len() != b.len() {
+                    return false;
+                }
+                let mut used = vec![false; b.len()];
+                a.iter().all(|x| {
+                    let found = b
+                        .iter()
+                        .enumerate()
+                        .position(|(i, y)| !used[i] && x.deep_equal_unordered(y));
+                    match found {
+                        Some(i) => {
+                            used[i] = true;
+                            true
+                        }
+                        None => false,
+                    }
+                })
+            }
+            (JsonValue::Object(a), JsonValue::Object(b)) => {
+                a.len() == b.len()
+                    && a.iter().all(|(k, x)| match b.get(k) {
+                        Some(y) => x.deep_equal_unordered(y),
+                        None => false,
+                    })
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    /// Slices of `size` consecutive array elements; the last one may be
+    /// shorter. `None` for non-arrays.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0, like `slice::chunks`.
+    pub fn array_chunks(&self, size: usize) -> Option<impl Iterator<Item = &[JsonValue]>> {
+        match self {
+            JsonValue::Array(arr) => Some(arr.chunks(size)),
+            _ => None,
+        }
+    }
+
+    /// Shallow preview of the value: arrays keep their first `n` elements and
+    /// objects keep `n` members (in map iteration order). Scalars are
+    /// returned as-is.
+    pub fn sample(&self, n: usize) -> JsonValue {
+        match self {
+            JsonValue::Array(arr) => JsonValue::Array(arr.iter().take(n).cloned().collect()),
+            JsonValue::Object(obj) => JsonValue::Object(
+                obj.iter()
+                    .take(n)
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect(),
+            ),
+            _ => self.clone(),
+        }
+    }
+
+    /// Iterate the numbers of an all-numeric array without collecting them.
+    ///
+    /// The array is validated upfront, so `None` is returned for non-arrays
+    /// and for arrays holding any non-number; the iterator itself never
+    /// has to report a failure halfway through.
+    pub fn f64_iter(&self) -> Option<impl Iterator<Item = f64> + '_> {
+        match self {
+            JsonValue::Array(arr) if arr.iter().all(|v| matches!(v, JsonValue::Num(_))) => {
+                Some(arr.iter().filter_map(|v| match v {
+                    JsonValue::Num(n) => Some(*n),
+                    _ => None,
+                }))
+            }
+            _ => None,
+        }
+    }
+
+    /// Visit this value and every nested value, parents before children.
+    fn walk<F: FnMut(&JsonValue)>(&self, f: &mut F) {
+        f(self);
+        match self {
+            JsonValue::Array(arr) => arr.iter().for_each(|v| v.walk(f)),
+            JsonValue::Object(obj) => obj.values().for_each(|v| v.walk(f)),
+            _ => {}
+        }
+    }
+
+    /// Mutable counterpart of `walk`.
+    fn walk_mut<F: FnMut(&mut JsonValue)>(&mut self, f: &mut F) {
+        f(self);
+        match self {
+            JsonValue::Array(arr) => arr.iter_mut().for_each(|v| v.walk_mut(f)),
+            JsonValue::Object(obj) => obj.values_mut().for_each(|v| v.walk_mut(f)),
+            _ => {}
+        }
+    }
+
+    /// Trim every string value in the tree and collapse each run of
+    /// whitespace inside it to a single space. Object keys are left alone.
+    ///
+    /// This rewrites string contents in place and cannot be undone.
+    pub fn collapse_string_whitespace(&mut self) {
+        self.walk_mut(&mut |v| {
+            if let JsonValue::Str(s) = v {
+                *s = s.split_whitespace().collect::<Vec<_>>().join(" ");
+            }
+        });
+    }
+
+    /// Number of nodes in the tree, this one included, that satisfy
+    /// `predicate`.
+    pub fn count_matching<P: Fn(&JsonValue) -> bool>(&self, predicate: P) -> usize {
+        let mut count = 0;
+        self.walk(&mut |v| {
+            if predicate(v) {
+                count += 1;
+            }
+        });
+        count
+    }
+
+    /// New object with the same keys and `f(key, value)` for each value;
+    /// only this level is visited. Anything other than an object is
+    /// returned as a plain copy.
+    pub fn map_object_values<F>(&self, f: F) -> JsonValue
+    where
+        F: Fn(&str, &JsonValue) -> JsonValue,
+    {
+        match self {
+            JsonValue::Object(obj) => {
+                JsonValue::Object(obj.iter().map(|(k, v)| (k.clone(), f(k, v))).collect())
+            }
+            other => other.clone(),
+        }
+    }
+
+    /// New array of the `Some` results of `f` on each element, in order.
+    /// Anything other than an array is returned as a plain copy.
+    pub fn filter_map_array<F>(&self, f: F) -> JsonValue
+    where
+        F: Fn(&JsonValue) -> Option<JsonValue>,
+    {
+        match self {
+            JsonValue::Array(arr) => JsonValue::Array(arr.iter().filter_map(f).collect()),
+            other => other.clone(),
+        }
+    }
+
+    /// Deep copy in which `f` may stand in for any node: where it returns
+    /// `Some`, that value is used as is and the node's children are not
+    /// visited; where it returns `None` the node is copied and its children
+    /// are offered to `f` in turn. Parents are offered before children.
+    pub fn clone_map<F>(&self, f: F) -> JsonValue
+    where
+        F: Fn(&JsonValue) -> Option<JsonValue>,
+    {
+        self.clone_map_with(&f)
+    }
+
+    fn clone_map_with<F>(&self, f: &F) -> JsonValue
+    where
+        F: Fn(&JsonValue) -> Option<JsonValue>,
+    {
+        if let Some(replacement) = f(self) {
+            return replacement;
+        }
+        match self {
+            JsonValue::Array(arr) => {
+                JsonValue::Array(arr.iter().map(|v| v.clone_map_with(f)).collect())
+            }
+            JsonValue::Object(obj) => JsonValue::Object(
+                obj.iter()
+                    .map(|(k, v)| (k.clone(), v.clone_map_with(f)))
+                    .collect(),
+            ),
+            scalar => scalar.clone(),
+        }
+    }
+
+    /// `true` if this value or any value nested in it equals `needle`.
+    ///
+    /// Whole values are compared with `==`, so `"ab"` does not contain
+    /// `"a"`, and object keys are not searched. Stops at the first match.
+    pub fn contains_value(&self, needle: &JsonValue) -> bool {
+        self == needle
+            || match self {
+                JsonValue::Array(arr) => arr.iter().any(|v| v.contains_value(needle)),
+                JsonValue::Object(obj) => obj.values().any(|v| v.contains_value(needle)),
+                _ => false,
+            }
+    }
+
+    /// Tally of every node in the tree, this one included, by kind.
+    /// Object keys are not counted as strings.
+    pub fn count_by_kind(&self) -> KindCounts {
+        let mut counts = KindCounts::default();
+        self.walk(&mut |v| match v {
+            JsonValue::Str(_) => counts.strings += 1,
+            JsonValue::Num(_) => counts.numbers += 1,
+            JsonValue::Boolean(_) => counts.booleans += 1,
+            JsonValue::Null => counts.nulls += 1,
+            JsonValue::Array(_) => counts.arrays += 1,
+            JsonValue::Object(_) => counts.objects += 1,
+        });
+        counts
+    }
+
+    /// Rough number of heap bytes owned by this value, for size-bounded
+    /// caches. Counts string and array capacities and, for objects, one
+    /// key, value and control byte per table slot, recursively; allocator
+    /// overhead is ignored. Unrelated to the serialized size.
+    pub fn heap_size(&self) -> usize {
+        match self {
+            JsonValue::Str(s) => s.capacity(),
+            JsonValue::Array(arr) => {
+                arr.capacity() * mem::size_of::<JsonValue>()
+                    + arr.iter().map(JsonValue::heap_size).sum::<usize>()
+            }
+            JsonValue::Object(obj) => {
+                let slot = mem::size_of::<String>() + mem::size_of::<JsonValue>() + 1;
+                obj.capacity() * slot
+                    + obj
+                        .iter()
+                        .map(|(k, v)| k.capacity() + v.heap_size())
+                        .sum::<usize>()
+            }
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::root;
+
+    #[test]
+    fn test_sample() {
+        let arr = JsonValue::Array(vec![
+            JsonValue::Num(1.0),
+            JsonValue::Num(2.0),
+            JsonValue::Num(3.0),
+        ]);
+        assert_eq!(
+            arr.sample(2),
+            JsonValue::Array(vec![JsonValue::Num(1.0), JsonValue::Num(2.0)])
+        );
+        assert_eq!(arr.sample(10), arr);
+
+        let obj: HashMap<String, JsonValue> = vec![
+            (String::from("a"), JsonValue::Null),
+            (String::from("b"), JsonValue::Boolean(true)),
+            (String::from("c"), JsonValue::Num(1.0)),
+        ]
+        .into_iter()
+        .collect();
+        match JsonValue::Object(obj.clone()).sample(2) {
+            JsonValue::Object(sampled) => {
+                assert_eq!(sampled.len(), 2);
+                for (k, v) in sampled.iter() {
+                    assert_eq!(obj.get(k), Some(v));
+                }
+            }
+            other => panic!("expected object, got {:?}", other),
+        }
+
+        assert_eq!(JsonValue::Null.sample(0), JsonValue::Null);
+    }
+
+    #[test]
+    fn test_f64_iter() {
+        let arr = JsonValue::Array((1..=1000).map(|i| JsonValue::Num(i as f64)).collect());
+        assert_eq!(arr.f64_iter().map(|it| it.sum::<f64>()), Some(500_500.0));
+        assert_eq!(
+            JsonValue::Array(vec![]).f64_iter().map(|it| it.count()),
+            Some(0)
+        );
+
+        let mixed = JsonValue::Array(vec![JsonValue::Num(1.0), JsonValue::Null]);
+        assert!(mixed.f64_iter().is_none());
+        assert!(JsonValue::Num(1.0).f64_iter().is_none());
+    }
+
+    #[test]
+    fn test_constructors() {
+        let v = JsonValue::object_from_pairs(vec![
+            (String::from("name"), JsonValue::Str(String::from("x"))),
+            (
+                String::from("tags"),
+                JsonValue::array_from_values(vec![
+                    JsonValue::Num(1.0),
+                    JsonValue::object_from_pairs(vec![]),
+                ]),
+            ),
+        ]);
+        let tags = JsonValue::Array(vec![JsonValue::Num(1.0), JsonValue::Object(HashMap::new())]);
+        let expected: HashMap<String, JsonValue> = vec![
+            (String::from("name"), JsonValue::Str(String::from("x"))),
+            (String::from("tags"), tags),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(v, JsonValue::Object(expected));
+        assert_eq!(
+            JsonValue::array_from_values(Vec::new()),
+            JsonValue::Array(vec![])
+        );
+    }
+
+    #[test]
+    fn test_is_scalar_is_container() {
+        let scalars = [
+            JsonValue::Str(String::from("s")),
+            JsonValue::Num(1.0),
+            JsonValue::Boolean(false),
+            JsonValue::Null,
+        ];
+        for v in scalars.iter() {
+            assert!(v.is_scalar());
+            assert!(!v.is_container());
+        }
+        let containers = [JsonValue::Array(vec![]), JsonValue::Object(HashMap::new())];
+        for v in containers.iter() {
+            assert!(v.is_container());
+            assert!(!v.is_scalar());
+        }
+    }
+
+    #[test]
+    fn test_count_matching() {
+        let (_, v) =
+            root(r#"{"a": null, "b": [null, 1, {"c": null, "d": 200}], "e": {}}"#).unwrap();
+        assert_eq!(v.count_matching(|v| *v == JsonValue::Null), 3);
+        assert_eq!(
+            v.count_matching(|v| matches!(v, JsonValue::Num(n) if *n > 100.0)),
+            1
+        );
+        assert_eq!(v.count_matching(|v| v.is_container()), 4);
+        assert_eq!(v.count_matching(|_| true), 9);
+    }
+
+    #[test]
+    fn test_map_object_values() {
+        let (_, v) = root(r#"{"a": 1, "b": 2.5, "c": "x", "d": [3]}"#).unwrap();
+        let doubled = v.map_object_values(|_, v| match v {
+            JsonValue::Num(n) => JsonValue::Num(n * 2.0),
+            other => other.clone(),
+        });
+        let (_, expected) = root(r#"{"a": 2, "b": 5, "c": "x", "d": [3]}"#).unwrap();
+        assert_eq!(doubled, expected);
+
+        let keys = v.map_object_values(|k, _| JsonValue::Str(String::from(k)));
+        assert_eq!(keys.get_str("c"), Some("c"));
+        let arr = JsonValue::Array(vec![JsonValue::Num(1.0)]);
+        assert_eq!(arr.map_object_values(|_, _| JsonValue::Null), arr);
+    }
+
+    #[test]
+    fn test_filter_map_array() {
+        let (_, v) =
+            root(r#"[{"id": 1, "on": true}, {"id": 2}, {"id": 3, "on": true}, 4]"#).unwrap();
+        let ids = v.filter_map_array(|item| match item.get_bool("on") {
+            Some(true) => item.get("id").cloned(),
+            _ => None,
+        });
+        assert_eq!(
+            ids,
+            JsonValue::Array(vec![JsonValue::Num(1.0), JsonValue::Num(3.0)])
+        );
+        assert_eq!(v.filter_map_array(|_| None), JsonValue::Array(vec![]));
+        let (_, obj) = root(r#"{"a": 1}"#).unwrap();
+        assert_eq!(obj.filter_map_array(|_| None), obj);
+    }
+
+    #[test]
+    fn test_clone_map() {
+        let (_, config) =
+            root(r#"{"db": {"host": "prod", "port": 5432}, "hosts": ["prod", "backup"]}"#).unwrap();
+        let prod = JsonValue::Str(String::from("prod"));
+        let local = config.clone_map(|v| {
+            if *v == prod {
+                Some(JsonValue::Str(String::from("localhost")))
+            } else {
+                None
+            }
+        });
+        let (_, expected) = root(
+            r#"{"db": {"host": "localhost", "port": 5432}, "hosts": ["localhost", "backup"]}"#,
+        )
+        .unwrap();
+        assert_eq!(local, expected);
+        assert_eq!(config.get("db").unwrap().get_str("host"), Some("prod"));
+
+        // a replaced node's children are not visited
+        let (_, db) = root(r#"{"port": 1}"#).unwrap();
+        let replaced = config.clone_map(|v| match v.get("host") {
+            Some(_) => Some(db.clone()),
+            None if *v == JsonValue::Num(1.0) => panic!("visited a replacement"),
+            None => None,
+        });
+        assert_eq!(replaced.pointer("/db/port"), Some(&JsonValue::Num(1.0)));
+        assert_eq!(config.clone_map(|_| None), config);
+    }
+
+    #[test]
+    fn test_count_by_kind() {
+        let (_, v) = root(
+            r#"{"a": ["x", 1, 2.5, true], "b": {"c": null, "d": [false, null, {}]}, "e": "y"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            v.count_by_kind(),
+            KindCounts {
+                strings: 2,
+                numbers: 2,
+                booleans: 2,
+                nulls: 2,
+                arrays: 2,
+                objects: 3,
+            }
+        );
+        let counts = JsonValue::Null.count_by_kind();
+        assert_eq!(counts.nulls, 1);
+        assert_eq!(counts.strings + counts.arrays + counts.objects, 0);
+    }
+
+    #[test]
+    fn test_contains_value() {
+        let (_, v) =
+            root(r#"{"a": [1, {"id": 42, "tags": ["x"]}], "name": "abc", "b": null}"#).unwrap();
+        let (_, nested) = root(r#"{"id": 42, "tags": ["x"]}"#).unwrap();
+        assert!(v.contains_value(&JsonValue::Num(42.0)));
+        assert!(v.contains_value(&nested));
+        assert!(v.contains_value(&JsonValue::Null));
+        assert!(v.contains_value(&v));
+        assert!(!v.contains_value(&JsonValue::Str(String::from("ab"))));
+        assert!(!v.contains_value(&JsonValue::Str(String::from("id"))));
+        assert!(!v.contains_value(&JsonValue::Num(43.0)));
+    }
+
+    #[test]
+    fn test_first_last() {
+        let empty = JsonValue::Array(vec![]);
+        assert_eq!(empty.first(), None);
+        assert_eq!(empty.last(), None);
+
+        let single = JsonValue::Array(vec![JsonValue::Null]);
+        assert_eq!(single.first(), Some(&JsonValue::Null));
+        assert_eq!(single.last(), Some(&JsonValue::Null));
+
+        let mut multi = JsonValue::Array(vec![
+            JsonValue::Num(1.0),
+            JsonValue::Num(2.0),
+            JsonValue::Num(3.0),
+        ]);
+        assert_eq!(multi.first(), Some(&JsonValue::Num(1.0)));
+        assert_eq!(multi.last(), Some(&JsonValue::Num(3.0)));
+        *multi.first_mut().unwrap() = JsonValue::Boolean(true);
+        *multi.last_mut().unwrap() = JsonValue::Boolean(false);
+        assert_eq!(
+            multi,
+            JsonValue::Array(vec![
+                JsonValue::Boolean(true),
+                JsonValue::Num(2.0),
+                JsonValue::Boolean(false)
+            ])
+        );
+
+        assert_eq!(JsonValue::Str(String::from("ab")).first(), None);
+        assert_eq!(JsonValue::Null.last_mut(), None);
+    }
+
+    #[test]
+    fn test_rename_key() {
+        let (_, mut v) = root(r#"{"userName": "x", "id": 1, "old": true}"#).unwrap();
+        assert!(v.rename_key("userName", "user_name"));
+        assert!(!v.rename_key("missing", "id"));
+        assert!(v.rename_key("old", "id"));
+        let (_, expected) = root(r#"{"user_name": "x", "id": true}"#).unwrap();
+        assert_eq!(v, expected);
+
+        assert!(!JsonValue::Array(vec![]).rename_key("a", "b"));
+    }
+
+    #[test]
+    fn test_try_as_integer() {
+        let (_, v) = root(r#"[300, 1.5, "7", null, [], -1, 255, 4294967295, 1e300]"#).unwrap();
+        let at = |i: usize| v.pointer(&format!("/{}", i)).unwrap();
+        assert_eq!(at(6).try_as_u8(), Ok(255));
+        assert_eq!(at(0).try_as_u16(), Ok(300));
+        assert_eq!(at(7).try_as_u32(), Ok(4294967295));
+        assert_eq!(at(5).try_as_i32(), Ok(-1));
+        assert_eq!(at(5).try_as_i64(), Ok(-1));
+        assert_eq!(at(0).try_as_u64(), Ok(300));
+
+        let message = |r: Result<u8, NumberError>| r.unwrap_err().to_string();
+        assert_eq!(message(at(0).try_as_u8()), "value 300 out of range for u8");
+        assert_eq!(message(at(1).try_as_u8()), "value 1.5 is not an integer");
+        assert_eq!(
+            message(at(2).try_as_u8()),
+            "value is a string, not a number"
+        );
+        assert_eq!(message(at(3).try_as_u8()), "value is null, not a number");
+        assert_eq!(
+            message(at(4).try_as_u8()),
+            "value is an array, not a number"
+        );
+        assert_eq!(
+            at(5).try_as_u32(),
+            Err(NumberError::OutOfRange {
+                value: -1.0,
+                target: "u32"
+            })
+        );
+        assert!(at(8).try_as_i64().is_err());
+        assert!(at(8).try_as_u64().is_err());
+    }
+
+    #[test]
+    fn test_typed_getters() {
+        let (_, v) =
+            root(r#"{"s": "x", "n": 1.5, "b": true, "a": [null], "o": {"k": 1}}"#).unwrap();
+        assert_eq!(v.get_str("s"), Some("x"));
+        assert_eq!(v.get_f64("n"), Some(1.5));
+        assert_eq!(v.get_bool("b"), Some(true));
+        assert_eq!(v.get_array("a"), Some(&[JsonValue::Null][..]));
+        assert_eq!(
+            v.get_object("o").and_then(|o| o.get("k")),
+            Some(&JsonValue::Num(1.0))
+        );
+
+        assert_eq!(v.get_str("missing"), None);
+        assert_eq!(v.get_str("n"), None);
+        assert_eq!(v.get_f64("s"), None);
+        assert_eq!(v.get_bool("a"), None);
+        assert_eq!(v.get_array("o"), None);
+        assert_eq!(v.get_object("b"), None);
+        assert_eq!(JsonValue::Array(vec![]).get("s"), None);
+
+        assert_eq!(JsonValue::null(), JsonValue::Null);
+        assert_eq!(v.get_array("a").unwrap()[0].as_null(), Some(()));
+        assert_eq!(v.get("s").and_then(JsonValue::as_null), None);
+        assert_eq!(v.get("missing").and_then(JsonValue::as_null), None);
+    }
+
+    #[test]
+    fn test_require_keys() {
+        let (_, v) = root(r#"{"id": 1, "name": null}"#).unwrap();
+        assert_eq!(v.require_keys(&["id", "name"]), Ok(()));
+        assert_eq!(v.require_keys(&[]), Ok(()));
+        assert_eq!(
+            v.require_keys(&["email", "id", "age"]),
+            Err(vec![String::from("email"), String::from("age")])
+        );
+        assert_eq!(
+            JsonValue::Array(vec![]).require_keys(&["id"]),
+            Err(vec![String::from("id")])
+        );
+        assert_eq!(JsonValue::Null.require_keys(&[]), Err(vec![]));
+    }
+
+    #[test]
+    fn test_collapse_string_whitespace() {
+        let mut v = JsonValue::object_from_pairs(vec![
+            (
+                String::from("  title  "),
+                JsonValue::Str(String::from("  Hello   \n\t world  ")),
+            ),
+            (
+                String::from("lines"),
+                JsonValue::Array(vec![
+                    JsonValue::Str(String::from("a\n\nb")),
+                    JsonValue::Str(String::from("   ")),
+                    JsonValue::Num(1.0),
+                ]),
+            ),
+        ]);
+        v.collapse_string_whitespace();
+        let expected = JsonValue::object_from_pairs(vec![
+            (
+                String::from("  title  "),
+                JsonValue::Str(String::from("Hello world")),
+            ),
+            (
+                String::from("lines"),
+                JsonValue::Array(vec![
+                    JsonValue::Str(String::from("a b")),
+                    JsonValue::Str(String::new()),
+                    JsonValue::Num(1.0),
+                ]),
+            ),
+        ]);
+        assert_eq!(v, expected);
+    }
+
+    #[test]
+    fn test_as_array_coerce() {
+        let (_, single) = root(r#"{"id": 1}"#).unwrap();
+        let (_, many) = root(r#"[{"id": 1}, {"id": 2}]"#).unwrap();
+        let ids = |v: &JsonValue| -> Vec<f64> {
+            v.as_array_coerce()
+                .iter()
+                .filter_map(|item| item.get_f64("id"))
+                .collect()
+        };
+        assert_eq!(ids(&single), vec![1.0]);
+        assert_eq!(ids(&many), vec![1.0, 2.0]);
+        assert!(JsonValue::Array(vec![]).as_array_coerce().is_empty());
+    }
+
+    #[test]
+    fn test_ensure_array_object() {
+        let (_, single) = root(r#"{"id": 1}"#).unwrap();
+        let (_, many) = root(r#"[{"id": 1}, {"id": 2}]"#).unwrap();
+        assert_eq!(many.clone().ensure_array(), many);
+        assert_eq!(
+            single.clone().ensure_array(),
+            JsonValue::Array(vec![single.clone()])
+        );
+        assert_eq!(
+            JsonValue::Null.ensure_array(),
+            JsonValue::Array(vec![JsonValue::Null])
+        );
+
+        assert_eq!(single.clone().ensure_object("item"), single);
+        let (_, wrapped) = root(r#"{"items": [{"id": 1}, {"id": 2}]}"#).unwrap();
+        assert_eq!(many.ensure_object("items"), wrapped);
+        assert_eq!(
+            JsonValue::Num(3.0).ensure_object("n").get_f64("n"),
+            Some(3.0)
+        );
+    }
+
+    #[test]
+    fn test_clear_truncate() {
+        let (_, mut obj) = root(r#"{"a": 1, "b": [2]}"#).unwrap();
+        obj.clear();
+        assert_eq!(obj, JsonValue::Object(HashMap::new()));
+
+        let (_, mut arr) = root("[1, 2, 3]").unwrap();
+        arr.truncate(5);
+        assert_eq!(arr.f64_iter().map(|it| it.count()), Some(3));
+        arr.truncate(1);
+        assert_eq!(arr, JsonValue::Array(vec![JsonValue::Num(1.0)]));
+        arr.clear();
+        assert_eq!(arr, JsonValue::Array(vec![]));
+
+        let mut s = JsonValue::Str(String::from("keep"));
+        s.clear();
+        s.truncate(0);
+        assert_eq!(s, JsonValue::Str(String::from("keep")));
+    }
+
+    #[test]
+    fn test_deep_equal_unordered() {
+        let parse = |s| root(s).unwrap().1;
+        assert!(parse("[1, 2, 3]").deep_equal_unordered(&parse("[3, 2, 1]")));
+        assert!(!parse("[1, 2, 3]").deep_equal_unordered(&parse("[1, 2, 2]")));
+        assert!(!parse("[1, 2, 2]").deep_equal_unordered(&parse("[1, 1, 2]")));
+        assert!(!parse("[1, 2]").deep_equal_unordered(&parse("[1, 2, 2]")));
+        assert!(parse(r#"{"a": [{"b": [1, 2]}, null]}"#)
+            .deep_equal_unordered(&parse(r#"{"a": [null, {"b": [2, 1]}]}"#)));
+        assert!(!parse(r#"{"a": [1]}"#).deep_equal_unordered(&parse(r#"{"b": [1]}"#)));
+        assert!(!parse("[[1, 2], [3]]").deep_equal_unordered(&parse("[[1], [2, 3]]")));
+    }
+
+    #[test]
+    fn test_array_chunks() {
+        let (_, v) = root("[1, 2, 3, 4, 5]").unwrap();
+        let sizes: Vec<usize> = v.array_chunks(2).unwrap().map(|c| c.len()).collect();
+        assert_eq!(sizes, vec![2, 2, 1]);
+        let last = v.array_chunks(2).unwrap().last().unwrap();
+        assert_eq!(last, &[JsonValue::Num(5.0)][..]);
+        assert_eq!(v.array_chunks(10).unwrap().count(), 1);
+        assert_eq!(JsonValue::Array(vec![]).array_chunks(3).unwrap().count(), 0);
+        assert!(JsonValue::Null.array_chunks(2).is_none());
+    }
+
+    #[test]
+    fn test_heap_size() {
+        assert_eq!(JsonValue::Num(1.0).heap_size(), 0);
+        assert_eq!(JsonValue::Null.heap_size(), 0);
+        assert!(JsonValue::Str("x".repeat(1000)).heap_size() >= 1000);
+
+        let record = r#"{"name": "some name", "tags": ["a", "b", "c"]}"#;
+        let (_, one) = root(&format!("[{}]", record)).unwrap();
+        let (_, ten) = root(&format!("[{}]", [record; 10].join(","))).unwrap();
+        let (one, ten) = (one.heap_size(), ten.heap_size());
+        assert!(one > 0);
+        assert!(ten >= 8 * one && ten <= 12 * one, "{} vs {}", one, ten);
+    }
+}